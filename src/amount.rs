@@ -0,0 +1,7 @@
+//! Arithmetic on msat amounts
+
+/// Sum msat amounts without overflow, the result is clamped to `u64::MAX`
+pub fn saturating_sum<I: Iterator<Item = u64>>(amounts: I) -> u64 {
+    let total: u128 = amounts.map(u128::from).sum();
+    u64::try_from(total).unwrap_or(u64::MAX)
+}
@@ -1,7 +1,11 @@
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use super::common::*;
+use super::version::Quirks;
+use crate::amount::saturating_sum;
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct AuditInfo {
     pub sent: Vec<SentInfo>,
@@ -9,12 +13,79 @@ pub struct AuditInfo {
     pub relayed: Vec<RelayedInfo>,
 }
 
-impl Default for AuditInfo {
-    fn default() -> AuditInfo {
-        AuditInfo {
-            sent: vec![],
-            received: vec![],
-            relayed: vec![],
+/// Parse the audit response in the shape that the node version gives
+pub fn parse_audit(txt: &str, quirks: Quirks) -> serde_json::Result<AuditInfo> {
+    if !quirks.millis_timestamps {
+        return serde_json::from_str(txt);
+    }
+    let mut value: Value = serde_json::from_str(txt)?;
+    timestamps_from_millis(&mut value);
+    serde_json::from_value(value)
+}
+
+/// Replace `timestamp` fields in unix milliseconds with objects of recent versions
+fn timestamps_from_millis(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match field.as_u64() {
+                    Some(millis) if key == "timestamp" => {
+                        let Timestamp { iso, unix } = Timestamp::from_millis(millis);
+                        *field = json!({ "iso": iso, "unix": unix });
+                    }
+                    _ => timestamps_from_millis(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(timestamps_from_millis),
+        _ => (),
+    }
+}
+
+impl AuditInfo {
+    /// Relays after `from` (unix time) that pass the filter, one record per payment.
+    /// Multi-part and trampoline relays of one payment are reported by eclair as several
+    /// records, so like eclair we count a payment hash once. The same goes for a record that
+    /// overlapping audit windows brought twice.
+    pub fn relays_since<'a, F>(
+        &'a self,
+        from: u64,
+        filter: F,
+    ) -> impl Iterator<Item = &'a RelayedInfo>
+    where
+        F: Fn(&RelayedInfo) -> bool + 'a,
+    {
+        self.relayed
+            .iter()
+            .filter(move |r| r.timestamp.unix > from && filter(r))
+            .unique_by(|r| r.payment_hash.clone())
+    }
+
+    /// Count, volume and fees of `relays_since`
+    pub fn relay_sums<F>(&self, from: u64, filter: F) -> RelaySums
+    where
+        F: Fn(&RelayedInfo) -> bool,
+    {
+        RelaySums::of(self.relays_since(from, filter))
+    }
+}
+
+/// Totals of a set of relays, amounts in msat
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct RelaySums {
+    pub count: u64,
+    pub volume: u64,
+    pub fee: u64,
+}
+
+impl RelaySums {
+    /// Sum the relays as they are, dedupe them before if needed
+    pub fn of<'a, I: Iterator<Item = &'a RelayedInfo>>(relays: I) -> Self {
+        let relays: Vec<&RelayedInfo> = relays.collect();
+        RelaySums {
+            count: relays.len() as u64,
+            volume: saturating_sum(relays.iter().map(|r| r.amount_in)),
+            fee: saturating_sum(relays.iter().map(|r| r.fee())),
         }
     }
 }
@@ -51,6 +122,22 @@ pub struct RelayedInfo {
     pub timestamp: Timestamp,
 }
 
+impl RelayedInfo {
+    /// Fee that the relay earned, msat
+    pub fn fee(&self) -> u64 {
+        self.amount_in.saturating_sub(self.amount_out)
+    }
+
+    /// Identity of the record, used to merge audit responses without losing any record.
+    /// Parts of one payment have different keys even though the stats count them once.
+    pub fn event_key(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.payment_hash, self.from_channel_id, self.to_channel_id, self.timestamp.unix
+        )
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SentPart {
@@ -0,0 +1,60 @@
+//! Backoff for polling a node that doesn't respond
+use std::time::Duration;
+
+/// Counts consecutive failed polls and tells how long to wait before the next one. After
+/// `threshold` failures in a row the breaker trips and the delay grows exponentially up to
+/// `max_delay`, so we don't hammer a node that is down.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failures: u32,
+    threshold: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        CircuitBreaker {
+            failures: 0,
+            threshold,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    /// Amount of failures in a row since the last successful poll
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// The breaker tripped after too many failures in a row
+    pub fn is_open(&self) -> bool {
+        self.failures >= self.threshold
+    }
+
+    /// Register successful poll, the breaker closes
+    pub fn success(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Forget the failures, e.g. when the user asks to retry right away
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Register failed poll
+    pub fn failure(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+    }
+
+    /// How long to wait before the next poll
+    pub fn delay(&self) -> Duration {
+        if !self.is_open() {
+            return self.base_delay;
+        }
+        let exponent = (self.failures - self.threshold + 1).min(16);
+        self.base_delay
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_delay)
+    }
+}
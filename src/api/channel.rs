@@ -13,11 +13,60 @@ pub struct ChannelInfo {
 }
 
 impl ChannelInfo {
+    /// Tell what kind of channel it is. `hosted` is whether a hosted channels plugin lists the
+    /// channel and `plugins_known` whether all installed hosted plugins answered.
+    pub fn kind(&self, hosted: bool, plugins_known: bool) -> ChannelKind {
+        if self.data.is_some() {
+            ChannelKind::Normal
+        } else if hosted {
+            ChannelKind::Hosted
+        } else if !plugins_known {
+            ChannelKind::Unclassified
+        } else {
+            ChannelKind::Dataless
+        }
+    }
+
     pub fn volume(&self) -> u64 {
         self.data.as_ref().map_or(0, |d| {
             let s = &d.commitments.local_commit.spec; s.to_local + s.to_remote
         })
     }
+
+    /// Reserve that the remote side requires us to keep in the channel, msat
+    pub fn reserve(&self) -> Option<u64> {
+        self.data
+            .as_ref()
+            .map(|d| d.commitments.remote_params.channel_reserve * 1000)
+    }
+
+    /// Local balance above the reserve that we are able to route, msat
+    pub fn spendable(&self) -> Option<u64> {
+        self.data.as_ref().map(|d| {
+            d.commitments
+                .local_commit
+                .spec
+                .to_local
+                .saturating_sub(d.commitments.remote_params.channel_reserve * 1000)
+        })
+    }
+
+    /// Feerate of the current local commitment transaction in sat/kw
+    pub fn feerate(&self) -> Option<u64> {
+        self.data
+            .as_ref()
+            .map(|d| d.commitments.local_commit.spec.commit_tx_feerate)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChannelKind {
+    Normal,
+    Hosted,
+    /// Listed without data while a hosted plugin can't be asked, it may be hosted
+    Unclassified,
+    /// Listed without data and no hosted plugin knows it, the node response is broken
+    Dataless,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
@@ -33,7 +82,12 @@ pub enum ChannelState {
     WaitForFundingConfirmed,
     WaitForFundingLocked,
     WaitForAcceptChannel,
+    WaitForDualFundingConfirmed,
+    WaitForDualFundingReady,
     Negotiating,
+    /// State that newer versions of eclair introduced and we don't know about yet
+    #[serde(other)]
+    Unknown,
 }
 
 impl ChannelState {
@@ -49,11 +103,18 @@ impl ChannelState {
         || self == ChannelState::WaitForFundingLocked
         || self == ChannelState::WaitForFundingSigned
         || self == ChannelState::WaitForAcceptChannel
+        || self == ChannelState::WaitForDualFundingConfirmed
+        || self == ChannelState::WaitForDualFundingReady
+        || self == ChannelState::Unknown
     }
 
     pub fn is_sleeping(self) -> bool {
         self == ChannelState::Offline
     }
+
+    pub fn is_closed(self) -> bool {
+        self == ChannelState::Closed
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
@@ -1,3 +1,4 @@
+use chrono::TimeZone;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
@@ -6,3 +7,15 @@ pub struct Timestamp {
     pub iso: String, 
     pub unix: u64,
 }
+
+impl Timestamp {
+    /// Timestamp from unix milliseconds, as eclair before 0.7.0 gives them
+    pub fn from_millis(millis: u64) -> Timestamp {
+        let unix = millis / 1000;
+        let nanos = (millis % 1000) as u32 * 1_000_000;
+        let iso = chrono::Utc
+            .timestamp(unix as i64, nanos)
+            .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+        Timestamp { iso, unix }
+    }
+}
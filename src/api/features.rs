@@ -0,0 +1,73 @@
+//! Human readable names of BOLT #9 features that nodes advertise
+use super::node::{FeatureStatus, NodeFeatures};
+
+/// Feature that we know how to describe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownFeature {
+    /// Name of the feature as eclair reports it
+    pub name: &'static str,
+    /// Mandatory (even) bit of the feature, the optional bit is the next one
+    pub bit: u32,
+    pub description: &'static str,
+}
+
+pub const KNOWN_FEATURES: &[KnownFeature] = &[
+    KnownFeature { name: "option_data_loss_protect", bit: 0, description: "Data loss protection" },
+    KnownFeature { name: "initial_routing_sync", bit: 2, description: "Initial routing sync" },
+    KnownFeature { name: "option_upfront_shutdown_script", bit: 4, description: "Upfront shutdown script" },
+    KnownFeature { name: "gossip_queries", bit: 6, description: "Gossip queries" },
+    KnownFeature { name: "var_onion_optin", bit: 8, description: "Variable size onion" },
+    KnownFeature { name: "gossip_queries_ex", bit: 10, description: "Extended gossip queries" },
+    KnownFeature { name: "option_static_remotekey", bit: 12, description: "Static remote key" },
+    KnownFeature { name: "payment_secret", bit: 14, description: "Payment secret" },
+    KnownFeature { name: "basic_mpp", bit: 16, description: "Multi-part payments" },
+    KnownFeature { name: "option_support_large_channel", bit: 18, description: "Large channels (wumbo)" },
+    KnownFeature { name: "option_anchor_outputs", bit: 20, description: "Anchor outputs" },
+    KnownFeature { name: "option_anchors_zero_fee_htlc_tx", bit: 22, description: "Anchors with zero fee HTLC" },
+    KnownFeature { name: "option_route_blinding", bit: 24, description: "Route blinding" },
+    KnownFeature { name: "option_shutdown_anysegwit", bit: 26, description: "Shutdown to any segwit" },
+    KnownFeature { name: "option_dual_fund", bit: 28, description: "Dual funding" },
+    KnownFeature { name: "option_onion_messages", bit: 38, description: "Onion messages" },
+    KnownFeature { name: "option_channel_type", bit: 44, description: "Explicit channel type" },
+    KnownFeature { name: "option_scid_alias", bit: 46, description: "Short channel id alias" },
+    KnownFeature { name: "option_payment_metadata", bit: 48, description: "Payment metadata" },
+    KnownFeature { name: "option_zeroconf", bit: 50, description: "Zero confirmation channels" },
+    KnownFeature { name: "keysend", bit: 54, description: "Spontaneous payments (keysend)" },
+    KnownFeature { name: "trampoline_payment", bit: 148, description: "Trampoline payments" },
+];
+
+/// Find feature by the name that eclair uses
+pub fn describe(name: &str) -> Option<&'static KnownFeature> {
+    KNOWN_FEATURES.iter().find(|f| f.name == name)
+}
+
+/// Find feature by either its mandatory or optional bit
+pub fn describe_bit(bit: u32) -> Option<&'static KnownFeature> {
+    KNOWN_FEATURES.iter().find(|f| f.bit == bit & !1)
+}
+
+/// Activated feature with resolved description
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedFeature {
+    pub name: String,
+    pub status: FeatureStatus,
+    /// None for features that we don't know about
+    pub known: Option<&'static KnownFeature>,
+}
+
+impl NodeFeatures {
+    /// Activated features sorted by bit, features unknown to us go last
+    pub fn decode(&self) -> Vec<DecodedFeature> {
+        let mut res: Vec<DecodedFeature> = self
+            .activated
+            .iter()
+            .map(|(name, status)| DecodedFeature {
+                name: name.clone(),
+                status: status.clone(),
+                known: describe(name),
+            })
+            .collect();
+        res.sort_by_key(|f| (f.known.map_or(u32::MAX, |k| k.bit), f.name.clone()));
+        res
+    }
+}
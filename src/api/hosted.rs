@@ -3,13 +3,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use super::channel::{ChannelState, ChannelUpdate, ChannelAnnouncement, CommitSpec, TlvStream};
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct FcInfo {
     pub channels: HashMap<String, FiatChannel>
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct HcInfo {
     pub channels: HashMap<String, HostedChannel>
@@ -43,6 +43,10 @@ pub struct FiatChanData {
     pub margin_proposal: Option<MarginProposal>,
     pub channel_announcement: Option<ChannelAnnouncement>,
     pub last_oracle_state: Option<u64>,
+    /// Ticker of the fiat currency of the channel. Older plugin versions don't send it, as
+    /// they support EUR only.
+    #[serde(default)]
+    pub currency: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
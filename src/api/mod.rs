@@ -1,17 +1,30 @@
 pub mod audit;
+pub mod breaker;
 pub mod channel;
 pub mod common;
+pub mod features;
 pub mod hosted;
 pub mod node;
+pub mod payment;
+pub mod peer;
+pub mod route;
+pub mod version;
 
 use self::{
     audit::AuditInfo,
     channel::ChannelInfo,
     hosted::{FcInfo, HcInfo},
     node::{NetworkNode, NodeInfo},
+    payment::IncomingPayment,
+    peer::PeerInfo,
+    route::RouteResponse,
+    version::Quirks,
 };
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyperlocal::{UnixClientExt, UnixConnector};
 use log::*;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use thiserror::Error;
 use std::time::Duration;
 
@@ -22,18 +35,91 @@ pub enum Error {
     ReqwestErr(#[from] reqwest::Error),
     #[error("Failed to decode: {0}")]
     DecodingErr(#[from] serde_json::Error),
+    #[error("Requesting unix socket error: {0}")]
+    HyperErr(#[from] hyper::Error),
+    #[error("Failed to build request: {0}")]
+    RequestErr(#[from] hyper::http::Error),
+    #[error("Failed to encode form: {0}")]
+    EncodingErr(#[from] serde_urlencoded::ser::Error),
+    #[error("Server responded with status: {0}")]
+    StatusErr(hyper::StatusCode),
+    #[error("Request timed out")]
+    TimeoutErr,
+    #[error("{0} endpoint returned non-JSON, check proxy/auth")]
+    NotJsonErr(&'static str),
+}
+
+/// Coarse classification of errors to show the user what is wrong with the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The node doesn't respond in time
+    Timeout,
+    /// Failed to establish connection with the node
+    Connect,
+    /// The node rejected our credentials
+    Unauthorized,
+    /// The node doesn't have the requested method
+    NotFound,
+    /// The node responded with something that we don't understand
+    Decode,
+    /// The node or its plugin failed to handle the request (5xx)
+    Server,
+    Other,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ReqwestErr(err) if err.is_timeout() => ErrorKind::Timeout,
+            Error::ReqwestErr(err) if err.is_connect() => ErrorKind::Connect,
+            Error::HyperErr(err) if err.is_timeout() => ErrorKind::Timeout,
+            Error::HyperErr(err) if err.is_connect() => ErrorKind::Connect,
+            Error::TimeoutErr => ErrorKind::Timeout,
+            Error::DecodingErr(_) | Error::NotJsonErr(_) => ErrorKind::Decode,
+            _ => match self.status() {
+                Some(401) => ErrorKind::Unauthorized,
+                Some(404) => ErrorKind::NotFound,
+                Some(500..=599) => ErrorKind::Server,
+                _ => ErrorKind::Other,
+            },
+        }
+    }
+
+    /// HTTP status of the failed response if the server responded at all
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::ReqwestErr(err) => err.status().map(|s| s.as_u16()),
+            Error::StatusErr(status) => Some(status.as_u16()),
+            _ => None,
+        }
+    }
 }
 
 /// Alias for a `Result` with the error type `self::Error`.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Fail with a clear error when the body is empty or obviously not JSON, e.g. an HTML
+/// error page of a misconfigured proxy. Decoding errors of such bodies say nothing useful.
+fn ensure_json(method: &'static str, body: &str) -> Result<()> {
+    match body.trim_start().chars().next() {
+        Some('{') | Some('[') => Ok(()),
+        _ => {
+            let preview: String = body.chars().take(80).collect();
+            warn!("Response of {} is not JSON: {:?}", method, preview);
+            Err(Error::NotJsonErr(method))
+        }
+    }
+}
+
 /// Additional plugins of Eclair node that we know about
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum NodePlugin {
     /// https://github.com/engenegr/plugin-hosted-channels
     HostedChannels,
     /// https://github.com/standardsats/plugin-fiat-channels
     FiatChannels,
+    /// Plugin declared by the user, detected by the API method that it adds
+    Custom { name: String, method: String },
 }
 
 impl std::fmt::Display for NodePlugin {
@@ -41,6 +127,24 @@ impl std::fmt::Display for NodePlugin {
         match self {
             NodePlugin::HostedChannels => write!(f, "hosted channels"),
             NodePlugin::FiatChannels => write!(f, "fiat channels"),
+            NodePlugin::Custom { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
+
+impl std::str::FromStr for NodePlugin {
+    type Err = String;
+
+    /// Parse custom plugin declaration in form of `name=method`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, method)) if !name.trim().is_empty() && !method.trim().is_empty() => {
+                Ok(NodePlugin::Custom {
+                    name: name.trim().to_owned(),
+                    method: method.trim().trim_start_matches('/').to_owned(),
+                })
+            }
+            _ => Err(format!("expected plugin as `name=method`, got `{}`", s)),
         }
     }
 }
@@ -49,36 +153,178 @@ impl NodePlugin {
     pub fn known() -> Vec<NodePlugin> {
         vec![NodePlugin::HostedChannels, NodePlugin::FiatChannels]
     }
+
+    /// API method that responds only when the plugin is installed
+    pub fn probe_method(&self) -> &str {
+        match self {
+            NodePlugin::HostedChannels => "hc-all",
+            NodePlugin::FiatChannels => "fc-all",
+            NodePlugin::Custom { method, .. } => method,
+        }
+    }
+}
+
+/// Response of commands for channels, maps channel ids to the result (e.g. closing txid)
+#[cfg(feature = "commands")]
+pub type ChannelsResponse = std::collections::HashMap<String, serde_json::Value>;
+
+/// Result of probing the node for known plugins
+#[derive(Debug, Clone, Default)]
+pub struct PluginsProbe {
+    /// Plugins that respond on their endpoints
+    pub supported: HashSet<NodePlugin>,
+    /// Plugins that we failed to probe (e.g. network blip), should be probed again later
+    pub unknown: HashSet<NodePlugin>,
+    /// Plugins whose endpoints exist but fail with server errors, with the error. They are
+    /// in `supported` as well.
+    pub erroring: HashMap<NodePlugin, String>,
+}
+
+/// Channels, peers and the audit that every poll requests, each may fail on its own
+#[derive(Debug)]
+pub struct NodeSnapshot {
+    pub channels: Result<Vec<ChannelInfo>>,
+    pub peers: Result<Vec<PeerInfo>>,
+    pub audit: Result<AuditInfo>,
+}
+
+/// How we reach the API of LN node
+#[derive(Clone)]
+enum Transport {
+    Http(reqwest::Client),
+    /// Path to the unix domain socket that the API listens on
+    Unix(hyper::Client<UnixConnector>, PathBuf),
+}
+
+/// URL scheme that selects unix domain socket transport, e.g. `unix:///run/eclair.sock`
+const UNIX_SCHEME: &str = "unix://";
+
+/// Timeout for requests to the node that should respond quickly
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Credentials that we send with every request
+#[derive(Clone)]
+enum Auth {
+    /// HTTP basic auth that eclair itself expects
+    Basic { user: String, password: String },
+    /// Token for auth gateways in front of the node
+    Bearer(String),
+}
+
+impl Auth {
+    /// Value of the `Authorization` header
+    fn header(&self) -> String {
+        match self {
+            Auth::Basic { user, password } => {
+                format!("Basic {}", base64::encode(format!("{}:{}", user, password)))
+            }
+            Auth::Bearer(token) => format!("Bearer {}", token),
+        }
+    }
 }
 
 /// Hold required information to query LN node
 #[derive(Clone)]
 pub struct Client {
     url: String,
-    user: String,
-    password: String,
-    client: reqwest::Client,
+    auth: Auth,
+    transport: Transport,
+    /// Plugins declared by the user that are probed along with the known ones
+    custom_plugins: Vec<NodePlugin>,
+    /// Differences of the node version in responses
+    quirks: Quirks,
 }
 
 impl Client {
     pub fn new(url: &str, user: &str, password: &str) -> Self {
+        let transport = match url.strip_prefix(UNIX_SCHEME) {
+            Some(path) => Transport::Unix(hyper::Client::unix(), PathBuf::from(path)),
+            None => Transport::Http(reqwest::Client::new()),
+        };
         Client {
             url: url.to_owned(),
-            user: user.to_owned(),
-            password: password.to_owned(),
-            client: reqwest::Client::new(),
+            auth: Auth::Basic {
+                user: user.to_owned(),
+                password: password.to_owned(),
+            },
+            transport,
+            custom_plugins: vec![],
+            quirks: Quirks::default(),
+        }
+    }
+
+    /// URL of the node API as given by the user
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Parse responses as the node version gives them, see `NodeInfo::parsed_version`
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Authenticate with `Authorization: Bearer <token>` instead of the basic auth
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.auth = Auth::Bearer(token.to_owned());
+        self
+    }
+
+    /// Probe also the given plugins in `get_supported_plugins`
+    pub fn with_plugins(mut self, plugins: Vec<NodePlugin>) -> Self {
+        self.custom_plugins = plugins;
+        self
+    }
+
+    /// Make POST request to the API method with form encoded params and return the response body
+    async fn request(
+        &self,
+        method: &str,
+        params: &[(&str, String)],
+        timeout: Option<Duration>,
+    ) -> Result<String> {
+        match &self.transport {
+            Transport::Http(client) => {
+                let mut builder = client
+                    .post(format!("{}/{}", self.url, method))
+                    .header(AUTHORIZATION, self.auth.header());
+                if !params.is_empty() {
+                    builder = builder.form(params);
+                }
+                if let Some(timeout) = timeout {
+                    builder = builder.timeout(timeout);
+                }
+                Ok(builder.send().await?.error_for_status()?.text().await?)
+            }
+            Transport::Unix(client, path) => {
+                let req = hyper::Request::post(hyper::Uri::from(hyperlocal::Uri::new(
+                    path,
+                    &format!("/{}", method),
+                )))
+                .header(AUTHORIZATION, self.auth.header())
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(hyper::Body::from(serde_urlencoded::to_string(params)?))?;
+                let response = async {
+                    let res = client.request(req).await?;
+                    if !res.status().is_success() {
+                        return Err(Error::StatusErr(res.status()));
+                    }
+                    let body = hyper::body::to_bytes(res.into_body()).await?;
+                    Ok(String::from_utf8_lossy(&body).into_owned())
+                };
+                match timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, response)
+                        .await
+                        .map_err(|_| Error::TimeoutErr)?,
+                    None => response.await,
+                }
+            }
         }
     }
 
     pub async fn get_info(&self) -> Result<NodeInfo> {
-        let builder = || {
-            self.client
-                .post(format!("{}/{}", self.url, "getinfo"))
-                .basic_auth(self.user.clone(), Some(self.password.clone()))
-                .timeout(Duration::from_secs(10))
-        };
         trace!("Requsting getinfo");
-        let txt = builder().send().await?.error_for_status()?.text().await?;
+        let txt = self.request("getinfo", &[], Some(REQUEST_TIMEOUT)).await?;
         trace!("Response from info: {}", txt);
         #[cfg(feature = "trace-to-file")]
         {
@@ -91,14 +337,8 @@ impl Client {
     }
 
     pub async fn get_channels(&self) -> Result<Vec<ChannelInfo>> {
-        let builder = || {
-            self.client
-                .post(format!("{}/{}", self.url, "channels"))
-                .basic_auth(self.user.clone(), Some(self.password.clone()))
-                .timeout(Duration::from_secs(10))
-        };
         trace!("Requsting channels");
-        let txt = builder().send().await?.error_for_status()?.text().await?;
+        let txt = self.request("channels", &[], Some(REQUEST_TIMEOUT)).await?;
         trace!("Response from channels: {}", txt);
         #[cfg(feature = "trace-to-file")]
         {
@@ -110,27 +350,30 @@ impl Client {
         Ok(serde_json::from_str(&txt)?)
     }
 
-    pub async fn get_audit(&self) -> Result<AuditInfo> {
-        const MONTH_PERIOD: i64 = 30 * 24 * 3600;
-        let now = chrono::offset::Utc::now().timestamp();
-        let mut params = HashMap::new();
-
-        // some day it may become aggregated yearly statistics
-        let to = now - 0 * MONTH_PERIOD;
-        let from = to - (0 + 1) * MONTH_PERIOD;
-        params.insert("from", from);
-        params.insert("to", to);
+    /// Query a single channel, cheaper than `get_channels` when only one channel is needed
+    pub async fn get_channel(&self, channel_id: &str) -> Result<ChannelInfo> {
+        trace!("Requsting channel {}", channel_id);
+        let params = [("channelId", channel_id.to_owned())];
+        let txt = self.request("channel", &params, Some(REQUEST_TIMEOUT)).await?;
+        trace!("Response from channel: {}", txt);
+        #[cfg(feature = "trace-to-file")]
+        {
+            if log_enabled!(log::Level::Trace) {
+                trace!("Response written to channel_response.json");
+                std::fs::write("channel_response.json", &txt).expect("Unable to write file");
+            }
+        }
+        Ok(serde_json::from_str(&txt)?)
+    }
 
-        let builder = || {
-            self.client
-                .post(format!("{}/{}", self.url, "audit"))
-                .basic_auth(self.user.clone(), Some(self.password.clone()))
-                .form(&params)
-                .timeout(Duration::from_secs(10))
-        };
+    /// Audit of the last `period` seconds
+    pub async fn get_audit(&self, period: i64) -> Result<AuditInfo> {
+        let to = chrono::offset::Utc::now().timestamp();
+        let from = to - period;
+        let params = [("from", from.to_string()), ("to", to.to_string())];
 
         trace!("Requsting audit from {}, to {}", from, to);
-        let txt = builder().send().await?.error_for_status()?.text().await?;
+        let txt = self.request("audit", &params, Some(REQUEST_TIMEOUT)).await?;
         trace!("Response from audit: {}", txt);
         #[cfg(feature = "trace-to-file")]
         {
@@ -139,22 +382,15 @@ impl Client {
                 std::fs::write("audit_response.json", &txt).expect("Unable to write file");
             }
         }
-        return Ok(serde_json::from_str(&txt)?);
+        ensure_json("audit", &txt)?;
+        Ok(audit::parse_audit(&txt, self.quirks)?)
     }
 
     /// Get information about given nodes
     pub async fn get_nodes(&self, ids: &[&str]) -> Result<Vec<NetworkNode>> {
-        let mut params = HashMap::new();
-        params.insert("nodeIds", ids.join(","));
-        let builder = || {
-            self.client
-                .post(format!("{}/{}", self.url, "nodes"))
-                .form(&params)
-                .basic_auth(self.user.clone(), Some(self.password.clone()))
-                .timeout(Duration::from_secs(10))
-        };
+        let params = [("nodeIds", ids.join(","))];
         trace!("Requsting nodes");
-        let txt = builder().send().await?.error_for_status()?.text().await?;
+        let txt = self.request("nodes", &params, Some(REQUEST_TIMEOUT)).await?;
         trace!("Response from nodes: {}", txt);
         #[cfg(feature = "trace-to-file")]
         {
@@ -164,54 +400,139 @@ impl Client {
             }
         }
 
+        Ok(node::parse_nodes(&txt)?)
+    }
+
+    pub async fn get_peers(&self) -> Result<Vec<PeerInfo>> {
+        trace!("Requsting peers");
+        let txt = self.request("peers", &[], Some(REQUEST_TIMEOUT)).await?;
+        trace!("Response from peers: {}", txt);
+        #[cfg(feature = "trace-to-file")]
+        {
+            if log_enabled!(log::Level::Trace) {
+                trace!("Response written to peers_response.json");
+                std::fs::write("peers_response.json", &txt).expect("Unable to write file");
+            }
+        }
+        Ok(serde_json::from_str(&txt)?)
+    }
+
+    /// Request channels, peers and the audit of the last `audit_period` seconds. They don't
+    /// depend on each other, so they go concurrently and a slow audit doesn't delay the rest.
+    pub async fn get_node_snapshot(&self, audit_period: i64) -> NodeSnapshot {
+        let (channels, peers, audit) = tokio::join!(
+            self.get_channels(),
+            self.get_peers(),
+            self.get_audit(audit_period)
+        );
+        NodeSnapshot {
+            channels,
+            peers,
+            audit,
+        }
+    }
+
+    /// Incoming payment with the given hash and its invoice. Eclair responds with 404 if
+    /// the node doesn't know the payment.
+    pub async fn get_received_info(&self, payment_hash: &str) -> Result<IncomingPayment> {
+        let params = [("paymentHash", payment_hash.to_owned())];
+        trace!("Requesting received info of {}", payment_hash);
+        let txt = self
+            .request("getreceivedinfo", &params, Some(REQUEST_TIMEOUT))
+            .await?;
+        trace!("Response from getreceivedinfo: {}", txt);
+        Ok(serde_json::from_str(&txt)?)
+    }
+
+    /// Incoming payments with the given hashes, queried concurrently. Results come in the
+    /// order of the hashes.
+    pub async fn get_received_infos(
+        &self,
+        hashes: Vec<String>,
+    ) -> Vec<(String, Result<IncomingPayment>)> {
+        let queries: Vec<_> = hashes
+            .into_iter()
+            .map(|hash| {
+                let client = self.clone();
+                tokio::spawn(async move {
+                    let info = client.get_received_info(&hash).await;
+                    (hash, info)
+                })
+            })
+            .collect();
+        let mut infos = Vec::with_capacity(queries.len());
+        for query in queries {
+            match query.await {
+                Ok(info) => infos.push(info),
+                Err(e) => warn!("Query of received info didn't finish: {}", e),
+            }
+        }
+        infos
+    }
+
+    /// Find routes from our node to the given node that are able to deliver the amount.
+    /// Eclair responds with 400 when there is no route.
+    pub async fn find_route(&self, node_id: &str, amount_msat: u64) -> Result<RouteResponse> {
+        let params = [
+            ("nodeId", node_id.to_owned()),
+            ("amountMsat", amount_msat.to_string()),
+        ];
+        trace!("Requesting route to {}", node_id);
+        let txt = self
+            .request("findroutetonode", &params, Some(REQUEST_TIMEOUT))
+            .await?;
+        trace!("Response from findroutetonode: {}", txt);
         Ok(serde_json::from_str(&txt)?)
     }
 
     /// Probe a specific endpoint for plugin to test it availability on remote node
-    pub async fn support_plugin(&self, plugin: NodePlugin) -> Result<bool> {
-        let method = match plugin {
-            NodePlugin::HostedChannels => format!("{}/{}", self.url, "hc-all"),
-            NodePlugin::FiatChannels => format!("{}/{}", self.url, "fc-all"),
-        };
+    pub async fn support_plugin(&self, plugin: &NodePlugin) -> Result<bool> {
         trace!("Checking if {plugin} is enabled at node");
-        let res = self
-            .client
-            .post(method)
-            .basic_auth(self.user.clone(), Some(self.password.clone()))
-            .send()
-            .await?;
-        match res.error_for_status() {
+        match self.request(plugin.probe_method(), &[], None).await {
             Ok(_) => Ok(true),
-            Err(err) => {
-                if err.status() == Some(reqwest::StatusCode::from_u16(404).unwrap()) {
-                    Ok(false)
-                } else {
-                    Err(err)?
-                }
+            Err(err) if err.status() == Some(404) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Probe all known and user declared plugins and collect supported ones to set
+    pub async fn get_supported_plugins(&self) -> PluginsProbe {
+        let mut plugins = NodePlugin::known();
+        for plugin in self.custom_plugins.iter() {
+            if !plugins.contains(plugin) {
+                plugins.push(plugin.clone());
             }
         }
+        self.probe_plugins(&plugins).await
     }
 
-    /// Probe all known plugins and collect supported ones to set
-    pub async fn get_supported_plugins(&self) -> Result<HashSet<NodePlugin>> {
-        let mut res = HashSet::new();
-        for plugin in NodePlugin::known() {
-            let supported = self.support_plugin(plugin).await?;
-            if supported {
-                res.insert(plugin);
+    /// Probe given plugins. Failure to probe a plugin doesn't abort the whole probing,
+    /// the plugin is marked as unknown instead.
+    pub async fn probe_plugins(&self, plugins: &[NodePlugin]) -> PluginsProbe {
+        let mut res = PluginsProbe::default();
+        for plugin in plugins {
+            match self.support_plugin(plugin).await {
+                Ok(true) => {
+                    res.supported.insert(plugin.clone());
+                }
+                Ok(false) => (),
+                Err(err) if err.kind() == ErrorKind::Server => {
+                    warn!("The {plugin} plugin is installed, but fails: {err}");
+                    res.supported.insert(plugin.clone());
+                    res.erroring.insert(plugin.clone(), err.to_string());
+                }
+                Err(err) => {
+                    warn!("Failed to probe {plugin} plugin, will retry later: {err}");
+                    res.unknown.insert(plugin.clone());
+                }
             }
         }
-        Ok(res)
+        res
     }
 
     pub async fn get_fiat_channels(&self) -> Result<FcInfo> {
-        let builder = || {
-            self.client
-                .post(format!("{}/{}", self.url, "fc-all"))
-                .basic_auth(self.user.clone(), Some(self.password.clone()))
-        };
         trace!("Requsting fc-all");
-        let txt = builder().send().await?.error_for_status()?.text().await?;
+        let txt = self.request("fc-all", &[], None).await?;
         trace!("Response from fc-all: {}", txt);
         #[cfg(feature = "trace-to-file")]
         {
@@ -224,13 +545,8 @@ impl Client {
     }
 
     pub async fn get_hosted_channels(&self) -> Result<HcInfo> {
-        let builder = || {
-            self.client
-                .post(format!("{}/{}", self.url, "hc-all"))
-                .basic_auth(self.user.clone(), Some(self.password.clone()))
-        };
         trace!("Requsting hc-all");
-        let txt = builder().send().await?.error_for_status()?.text().await?;
+        let txt = self.request("hc-all", &[], None).await?;
         trace!("Response from hc-all: {}", txt);
         #[cfg(feature = "trace-to-file")]
         {
@@ -241,4 +557,35 @@ impl Client {
         }
         Ok(serde_json::from_str(&txt)?)
     }
+
+    /// Close the channel cooperatively or unilaterally if `force` is set
+    #[cfg(feature = "commands")]
+    pub async fn close_channel(&self, channel_id: &str, force: bool) -> Result<ChannelsResponse> {
+        let method = if force { "forceclose" } else { "close" };
+        let params = [("channelId", channel_id.to_owned())];
+        info!("Requesting {} of channel {}", method, channel_id);
+        let txt = self.request(method, &params, Some(REQUEST_TIMEOUT)).await?;
+        info!("Response from {}: {}", method, txt);
+        Ok(serde_json::from_str(&txt)?)
+    }
+
+    /// Connect to the peer by URI in form of `nodeId@host:port`
+    #[cfg(feature = "commands")]
+    pub async fn connect(&self, node_uri: &str) -> Result<String> {
+        let params = [("uri", node_uri.to_owned())];
+        info!("Requesting connect to {}", node_uri);
+        let txt = self.request("connect", &params, Some(REQUEST_TIMEOUT)).await?;
+        info!("Response from connect: {}", txt);
+        Ok(serde_json::from_str(&txt)?)
+    }
+
+    /// Disconnect from the peer, channels with it become offline
+    #[cfg(feature = "commands")]
+    pub async fn disconnect(&self, node_id: &str) -> Result<String> {
+        let params = [("nodeId", node_id.to_owned())];
+        info!("Requesting disconnect from {}", node_id);
+        let txt = self.request("disconnect", &params, Some(REQUEST_TIMEOUT)).await?;
+        info!("Response from disconnect: {}", txt);
+        Ok(serde_json::from_str(&txt)?)
+    }
 }
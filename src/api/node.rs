@@ -1,7 +1,9 @@
 
+use log::warn;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use super::common::*;
+use super::version::Version;
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +20,13 @@ pub struct NodeInfo {
     pub instance_id: String,
 }
 
+impl NodeInfo {
+    /// None if eclair reports the version in unknown format
+    pub fn parsed_version(&self) -> Option<Version> {
+        Version::parse(&self.version)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct NodeFeatures {
@@ -50,4 +59,27 @@ pub struct NetworkNode {
     pub rgb_color: String,
     pub alias: String,
     pub addresses: Vec<String>,
-}
\ No newline at end of file
+}
+
+/// Parse response of `nodes` entry by entry. Gossip of a single node may be weird, so
+/// entries that we cannot parse are skipped with a warning instead of failing the whole list.
+pub fn parse_nodes(json: &str) -> serde_json::Result<Vec<NetworkNode>> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let node_id = entry.get("nodeId").and_then(|id| id.as_str()).map(str::to_owned);
+            match serde_json::from_value(entry) {
+                Ok(node) => Some(node),
+                Err(e) => {
+                    warn!(
+                        "Skipping malformed node {}: {}",
+                        node_id.as_deref().unwrap_or("without id"),
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .collect())
+}
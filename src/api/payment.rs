@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use super::common::*;
+
+/// Response of `getreceivedinfo`, incoming payment with its invoice
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IncomingPayment {
+    /// Newer eclair versions call it `invoice`
+    #[serde(alias = "invoice")]
+    pub payment_request: PaymentRequest,
+    pub payment_preimage: String,
+    pub payment_type: String,
+    pub created_at: Timestamp,
+    pub status: IncomingStatus,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequest {
+    pub payment_hash: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Requested amount in msat, invoices may leave it to the payer
+    #[serde(default)]
+    pub amount: Option<u64>,
+    /// Seconds after creation when the invoice expires
+    #[serde(default)]
+    pub expiry: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IncomingStatus {
+    Pending,
+    Expired,
+    #[serde(rename_all = "camelCase")]
+    Received {
+        /// Received amount in msat, may exceed the requested one
+        amount: u64,
+        received_at: Timestamp,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl IncomingStatus {
+    /// The status doesn't change anymore, so there is no need to query it again
+    pub fn is_final(&self) -> bool {
+        matches!(self, IncomingStatus::Received { .. } | IncomingStatus::Expired)
+    }
+}
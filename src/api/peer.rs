@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    pub node_id: String,
+    pub state: PeerState,
+    /// Present only for connected peers
+    pub address: Option<String>,
+    pub channels: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PeerState {
+    Instantiating,
+    Disconnected,
+    Connected,
+}
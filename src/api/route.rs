@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of `findroutetonode`, the best route goes first
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct RouteResponse {
+    pub routes: Vec<Route>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Route {
+    /// Amount in msat that the route is able to deliver
+    pub amount: u64,
+    /// Nodes of the route starting from our node
+    pub node_ids: Vec<String>,
+}
@@ -0,0 +1,72 @@
+//! Versions of eclair that we are able to parse responses from
+use std::fmt;
+
+/// Semantic version of the node, commit suffix like in `0.6.2-e1f6a9a` is dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse `major.minor[.patch]` with optional `-suffix`
+    pub fn parse(s: &str) -> Option<Version> {
+        let core = s.trim().trim_start_matches('v').split(['-', '+']).next()?;
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = match parts.next() {
+            Some(patch) => patch.parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Version::new(major, minor, patch))
+    }
+
+    /// The version is in `SUPPORTED_VERSION_RANGE`
+    pub fn is_supported(&self) -> bool {
+        let (min, max) = SUPPORTED_VERSION_RANGE;
+        min <= *self && *self < max
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Versions of eclair we tested against, including the first and excluding the last one
+pub const SUPPORTED_VERSION_RANGE: (Version, Version) =
+    (Version::new(0, 6, 0), Version::new(0, 9, 0));
+
+/// First version that gives timestamps as objects with `iso` and `unix` (seconds) fields,
+/// earlier versions give unix milliseconds
+pub const OBJECT_TIMESTAMPS_SINCE: Version = Version::new(0, 7, 0);
+
+/// Differences in responses of eclair versions that we parse around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quirks {
+    /// Timestamps in the audit are unix milliseconds
+    pub millis_timestamps: bool,
+}
+
+impl Quirks {
+    /// Quirks of the node version, a node of unknown version is taken for a recent one
+    pub fn of(version: Option<Version>) -> Quirks {
+        Quirks {
+            millis_timestamps: version.is_some_and(|v| v < OBJECT_TIMESTAMPS_SINCE),
+        }
+    }
+}
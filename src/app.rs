@@ -1,19 +1,39 @@
 use crossterm::event::KeyCode;
 use itertools::Itertools;
 use log::*;
+use num_format::{Locale, ToFormattedString};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
+use std::iter;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::Notify;
 
+use super::amount::saturating_sum;
 use super::api::{
-    audit::{AuditInfo, RelayedInfo},
-    channel::{ChannelInfo, ChannelState},
-    hosted::{FcInfo, FiatChannel, HcInfo, HostedChannel},
+    audit::{AuditInfo, RelaySums, RelayedInfo},
+    breaker::CircuitBreaker,
+    channel::{ChannelInfo, ChannelKind, ChannelState},
+    hosted::{FcInfo, FiatChannel, HcInfo, HostedChannel, MarginProposal, ResizeProposal},
     node::{NetworkNode, NodeInfo},
-    Client, NodePlugin,
+    payment::{IncomingPayment, IncomingStatus},
+    peer::PeerInfo,
+    version::{Quirks, SUPPORTED_VERSION_RANGE},
+    Client, ErrorKind, NodePlugin,
 };
+use super::commands::{Command, RouteQuery};
+use super::fifo::StatsFifo;
+use super::format::{abbreviate, scale_line, AmountUnit, DisplayTz};
+use super::history::{self, OFFLINE_STATES};
+use super::opts::Opts;
+use super::price::PriceSource;
+use super::reachability;
+use super::storage;
+use super::supervise::{lock, restart_on_exit};
+use super::theme::Theme;
+use super::valuation::{self, ChannelRate, FiatValuation, OracleRate, RateSource};
 
 pub type AppMutex = Arc<Mutex<App>>;
 
@@ -21,18 +41,94 @@ pub struct App {
     pub client: Client,
     pub db: sled::Db,
 
-    pub tabs: Vec<String>,
+    /// Tabs that are currently shown, depends on supported plugins and user preferences
+    pub tabs: Vec<Tab>,
+    /// Index of the current tab in `tabs`
     pub tab_index: usize,
+    pub hide_hosted: bool,
+    pub hide_fiat: bool,
 
     pub errors: Vec<String>,
+    /// Maximum length of `errors`
+    pub max_errors: usize,
+    /// How many errors were dropped from the popup to keep it within `max_errors`
+    pub errors_dropped: usize,
+    /// Seconds after which the errors popup is closed automatically
+    pub errors_timeout: Option<i64>,
+    /// When the popup got its last error or lost the topmost one
+    pub errors_shown_at: i64,
+    /// Seconds during which a dismissed error doesn't reopen the popup
+    pub errors_cooldown: i64,
+    /// When errors shown in the popup were dismissed last time
+    pub dismissed_errors: HashMap<String, i64>,
+    pub error_log: Vec<ErrorEntry>,
+    pub connection: ConnectionStatus,
+    /// The poller gave up on regular polling after too many failures in a row
+    pub breaker_open: bool,
+    /// Unix timestamp of the next poll after a failed one
+    pub next_retry: Option<i64>,
+    /// The user asked to reconnect right away, the worker resets the backoff
+    pub reconnect_requested: bool,
+
+    /// Wakes up the worker to query the node before the next poll
+    pub refresh: Arc<Notify>,
+    /// Wake up the worker when the terminal gains focus
+    pub refresh_on_focus: bool,
+    /// Panic in the draw function, see `--debug-panic`
+    pub debug_panic: bool,
+
+    pub commands_enabled: bool,
+    /// Command that waits for confirmation from the user
+    pub confirm_command: Option<Command>,
+    /// Confirmed command that waits to be executed
+    pub queued_command: Option<Command>,
+    /// Route query from the prompt that waits to be sent
+    pub queued_route: Option<RouteQuery>,
+    pub command_result: Option<String>,
 
     pub supported: HashSet<NodePlugin>,
+    pub unknown_plugins: HashSet<NodePlugin>,
+    /// Errors of installed plugins whose endpoints fail, their tabs show the error
+    pub plugin_errors: HashMap<NodePlugin, String>,
+    /// Channels without data that were already reported
+    pub warned_dataless: HashSet<String>,
     pub stats_interval: i64,
+    /// Relays with bigger fee to amount ratio are treated as malformed
+    pub max_fee_ratio: f64,
+    pub price_source: Option<PriceSource>,
+    pub price_currency: String,
+    /// Decimal places of displayed fiat amounts
+    pub fiat_decimals: usize,
+    /// Rate source that values balances of fiat channels
+    pub fiat_valuation: FiatValuation,
+    /// Price of 1 BTC, None when we don't have a fresh price
+    pub btc_price: Option<f64>,
+    /// Spendable balance (msat) above the reserve below which channel is flagged
+    pub reserve_buffer: u64,
+    /// Channels offline for more days are zombies
+    pub zombie_days: i64,
+    /// Channels syncing or waiting for the funding for more hours are stuck
+    pub stuck_hours: i64,
+    /// Recent state changes of channels, by channel id
+    pub state_history: HashMap<String, Vec<storage::Snapshot<ChannelState>>>,
+    /// When channels went offline or started syncing, by channel id
+    pub sleeping_since: HashMap<String, i64>,
+    /// Where `w` writes the channels leaderboard
+    pub leaderboard_path: String,
+    /// Pipe that gets Dashboard stats after each poll
+    pub stats_fifo: Option<StatsFifo>,
+    /// Channels that are always shown first in lists
+    pub pinned: HashSet<String>,
 
     pub node_info: NodeInfo,
+    /// Unix timestamp of the last successful poll of the node
+    pub last_update: Option<i64>,
+    /// Set when the node runs eclair version that we didn't test against
+    pub version_warning: Option<String>,
     pub active_chans: usize,
     pub pending_chans: usize,
     pub sleeping_chans: usize,
+    pub closed_chans: usize,
 
     pub active_sats: u64,
     pub pending_sats: u64,
@@ -45,31 +141,626 @@ pub struct App {
 
     pub fee_month: u64,
     pub fee_day: u64,
+    /// Fees that the node paid for its own payments over the month
+    pub fees_paid_month: u64,
+    /// Relays counted since the first run
+    pub relays_lifetime: RelayLifetime,
+    /// When relays that can't be in the audit anymore were last removed from the seen set
+    relays_pruned: i64,
+    /// Period of the second row of relay figures on the dashboard
+    pub stats_period: StatsPeriod,
+    pub relayed_count_period: u64,
+    pub relayed_period: u64,
+    pub fee_period: u64,
+    /// Period covered by the last audit that we got from the node
+    pub audit_fetched_period: i64,
+    /// When the whole audit period was fetched last time
+    audit_full_at: i64,
+    /// When any part of the audit was fetched last time, the next poll continues from it
+    audit_fetched_at: i64,
     pub return_rate: f64, // ARP per year
+    pub return_rate_provisional: bool,
+    /// Relays totals of hosted channels only
+    pub hosted_totals: RelayTotals,
+    pub median_feerate: Option<u64>,
+    /// Duplicate scids and other oddities of the channels list
+    pub channel_anomalies: Vec<ChannelAnomaly>,
 
     pub screen_width: u16,
+    pub screen_height: u16,
+    /// Rows of the compact dashboard list in the last drawn frame, its page size
+    pub compact_rows: std::cell::Cell<usize>,
     pub relays_maximum_volume: u64,
     pub relays_maximum_count: u64,
     pub relays_amounts_line: Vec<u64>,
     pub relays_volumes_line: Vec<u64>,
+    /// Same as relays lines but only with relays that touch fiat channels
+    pub fiat_relays_maximum_volume: u64,
+    pub fiat_relays_maximum_count: u64,
+    pub fiat_relays_amounts_line: Vec<u64>,
+    pub fiat_relays_volumes_line: Vec<u64>,
+    /// Same as relays lines but only with relays that touch hosted channels
+    pub hosted_relays_maximum_volume: u64,
+    pub hosted_relays_maximum_count: u64,
+    pub hosted_relays_amounts_line: Vec<u64>,
+    pub hosted_relays_volumes_line: Vec<u64>,
+    /// Amount of buckets of the 24h sparklines, by default one per cell
+    pub sparkline_buckets: Option<u64>,
+    /// Scale the volumes sparkline logarithmically, so small relays stay visible near outliers
+    pub volumes_log_scale: bool,
+    pub relays_hourly_counts: Vec<(String, u64)>,
+    pub relays_hourly_volumes: Vec<(String, u64)>,
+    /// Fees in sats earned in each of the last days, labeled by day of month
+    pub daily_fees: Vec<(String, u64)>,
 
     pub channels_stats: Vec<ChannelStats>,
     pub hosted_stats: Vec<ChannelStats>,
     pub fiat_stats: Vec<ChannelStats>,
+    /// Last rate change of fiat channels by channel id, see `fiat_rate_delta`
+    pub fiat_rate_deltas: HashMap<String, i64>,
+    /// Recorded relayed volumes by channel id, see `channel_volume_history`
+    pub channel_volumes: HashMap<String, Vec<u64>>,
+    /// Recorded daily fees by channel id, see `channel_fee_history`
+    pub channel_fees: HashMap<String, Vec<u64>>,
 
     pub channels: Vec<ChannelInfo>,
+    pub peers: Vec<PeerInfo>,
     pub audit: AuditInfo,
     pub known_nodes: HashMap<String, NetworkNode>,
+    /// Invoices of received payments by payment hash, `None` if the node doesn't know it
+    pub incoming_payments: HashMap<String, Option<IncomingPayment>>,
+    /// When we last queried invoices that failed or may still change their statuses, by
+    /// payment hash. They are queried again after a delay.
+    pub incoming_queried: HashMap<String, i64>,
+    /// Labels of peers from `--alias-map`, they take precedence over gossip aliases
+    pub alias_map: HashMap<String, String>,
+    /// Probe advertised addresses with TCP connections
+    pub check_reachability: bool,
+    /// Whether addresses accepted connection at the last probe
+    pub reachability: HashMap<String, bool>,
+    /// When we queried the node info last time, including nodes that the node doesn't know
+    pub nodes_fetched: HashMap<String, i64>,
     pub hc_channels: HashMap<String, HostedChannel>,
     pub fc_channels: HashMap<String, FiatChannel>,
 
+    /// Hide idle channels on Channels, Hosted and Fiat screens
+    pub hide_idle: bool,
+    /// Channels whose local balance changed at the last poll with ticks left to highlight them
+    pub balance_flashes: HashMap<String, u32>,
+
+    /// Gauges show remote balance as the filled part
+    pub gauge_remote: bool,
+    pub amount_unit: AmountUnit,
+    /// Abbreviate amounts in compact channel widgets, e.g. `1.2M sats`
+    pub abbreviate_amounts: bool,
+    /// Mask identities and balances on screen, toggled with `A`
+    pub anonymize: bool,
+    /// Anonymized balances are hidden instead of rounded
+    pub anonymize_hide_balances: bool,
+
     // Dashboard screen
+    /// Draw a single line per channel instead of cards
+    pub dashboard_compact: bool,
+    pub chans_sort: ChannelSort,
+    pub timezone: DisplayTz,
+    pub theme: Theme,
+    /// The prompt is open and takes all keys
     pub search_focused: bool,
+    /// What the open prompt asks for
+    pub prompt: Prompt,
+    /// Query typed in the prompt
     pub search_line: String,
     pub channels_page: u64,
 
     // Channels screen
     pub chans_tab: usize,
+    /// Row of the cursor, only a fallback when the channel is tracked by `chans_selected_id`
+    pub chans_selected: usize,
+    /// Selected channel, polls re-sort the list so the row alone would move the cursor to
+    /// another channel
+    pub chans_selected_id: Option<String>,
+    pub detail_open: bool,
+    /// Show channels collapsed by peer
+    pub chans_grouped: bool,
+
+    // Peers screen
+    pub peers_selected: usize,
+    pub peers_sort: PeerSort,
+
+    // Log screen
+    pub error_log_scroll: usize,
+
+    // Node features popup
+    pub features_open: bool,
+}
+
+/// Parsed node data and computed stats, dumped with `--dump-state` to reproduce rendering
+/// issues. It has nothing from the API client, so the password never gets into the dump.
+#[derive(Serialize)]
+pub struct AppSnapshot<'a> {
+    pub node_info: &'a NodeInfo,
+    pub supported: Vec<String>,
+    pub stats_interval: i64,
+    pub channels: &'a [ChannelInfo],
+    pub peers: &'a [PeerInfo],
+    pub hc_channels: &'a HashMap<String, HostedChannel>,
+    pub fc_channels: &'a HashMap<String, FiatChannel>,
+    pub audit: &'a AuditInfo,
+    pub known_nodes: &'a HashMap<String, NetworkNode>,
+    pub channels_stats: &'a [ChannelStats],
+    pub hosted_stats: &'a [ChannelStats],
+    pub fiat_stats: &'a [ChannelStats],
+    pub pending_proposals: Vec<(&'a str, Proposal)>,
+    pub active_sats: u64,
+    pub pending_sats: u64,
+    pub sleeping_sats: u64,
+    pub relayed_count_month: u64,
+    pub relayed_count_day: u64,
+    pub relayed_month: u64,
+    pub relayed_day: u64,
+    pub fee_month: u64,
+    pub fee_day: u64,
+    pub fees_paid_month: u64,
+    pub return_rate: f64,
+    pub hosted_totals: &'a RelayTotals,
+}
+
+/// Headline numbers of the Dashboard, streamed with `--stats-fifo` as one JSON line per poll
+#[derive(Serialize)]
+pub struct DashboardSnapshot<'a> {
+    /// Unix timestamp of the poll
+    pub timestamp: Option<i64>,
+    pub node_id: &'a str,
+    pub alias: &'a str,
+    pub block_height: u64,
+    pub active_chans: usize,
+    pub pending_chans: usize,
+    pub sleeping_chans: usize,
+    pub active_sats: u64,
+    pub pending_sats: u64,
+    pub sleeping_sats: u64,
+    pub pending_htlcs: usize,
+    pub pending_htlcs_amount: u64,
+    pub relayed_count_month: u64,
+    pub relayed_count_day: u64,
+    pub relayed_month: u64,
+    pub relayed_day: u64,
+    pub fee_month: u64,
+    pub fee_day: u64,
+    pub fees_paid_month: u64,
+    pub relays_lifetime: RelayLifetime,
+    pub return_rate: f64,
+    pub return_rate_provisional: bool,
+    pub btc_price: Option<f64>,
+}
+
+/// Suspicious relay fee policy of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeePolicyFlag {
+    /// The channel routes for free, leaving money on the table
+    FreeWhileRouting,
+    /// The fee is so high that nothing is routed through the channel
+    ExpensiveWhileIdle,
+}
+
+impl std::fmt::Display for FeePolicyFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FeePolicyFlag::FreeWhileRouting => write!(f, "zero fee while routing"),
+            FeePolicyFlag::ExpensiveWhileIdle => write!(f, "high fee while idle"),
+        }
+    }
+}
+
+/// Inconsistency of the node state found in the list of channels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelAnomaly {
+    /// Several channels claim the same short channel id, gossip or the DB is inconsistent
+    DuplicateScid { scid: String, count: usize },
+    /// Unusually many channels are opened with one peer
+    ManyChannels { node_id: String, count: usize },
+}
+
+impl std::fmt::Display for ChannelAnomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChannelAnomaly::DuplicateScid { scid, count } => {
+                write!(f, "{} channels share short channel id {}", count, scid)
+            }
+            ChannelAnomaly::ManyChannels { node_id, count } => {
+                write!(f, "{} channels are opened with peer {}", count, node_id)
+            }
+        }
+    }
+}
+
+/// What the text typed in the prompt is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    JumpToPeer,
+    /// Query for `RouteQuery`, only with commands enabled
+    FindRoute,
+}
+
+impl Prompt {
+    pub fn label(self) -> &'static str {
+        match self {
+            Prompt::JumpToPeer => "Jump to peer",
+            Prompt::FindRoute => "Find route to <node id> <sats>",
+        }
+    }
+}
+
+/// Order of channels on the Channels screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelSort {
+    /// Biggest channels first
+    Capacity,
+    /// Channels with the highest `ChannelStats::relay_velocity` first
+    Velocity,
+}
+
+/// Longer period of relay figures on the dashboard, cycled with `t`. Day figures are
+/// always shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    Year,
+}
+
+impl StatsPeriod {
+    pub fn next(self) -> Self {
+        match self {
+            StatsPeriod::Week => StatsPeriod::Month,
+            StatsPeriod::Month => StatsPeriod::Year,
+            StatsPeriod::Year => StatsPeriod::Week,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "week",
+            StatsPeriod::Month => "month",
+            StatsPeriod::Year => "year",
+        }
+    }
+
+    pub fn seconds(self) -> i64 {
+        match self {
+            StatsPeriod::Week => 7 * 24 * 3600,
+            StatsPeriod::Month => App::MONTH_PERIOD,
+            StatsPeriod::Year => App::YEAR_PERIOD,
+        }
+    }
+}
+
+/// Order of peers on the Peers screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerSort {
+    /// As the node lists them
+    Node,
+    /// Peers with the most recent relay through their channels first
+    LastRelay,
+}
+
+/// Row of the channels leaderboard export, amounts are in msat
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub alias: String,
+    pub channel_id: String,
+    pub relays: u64,
+    pub volume: u64,
+    /// Volume of relays that came into the node through the channel
+    pub volume_in: u64,
+    /// Volume of relays that left the node through the channel
+    pub volume_out: u64,
+    pub fees: u64,
+    /// Earned fees in parts per million of the routed volume
+    pub fee_ppm: u64,
+}
+
+impl LeaderboardEntry {
+    const CSV_HEADER: &'static str =
+        "rank,alias,channel_id,relays,volume,volume_in,volume_out,fees,fee_ppm";
+
+    fn to_csv(&self) -> String {
+        format!(
+            "{},\"{}\",{},{},{},{},{},{},{}",
+            self.rank,
+            self.alias.replace('"', "\"\""),
+            self.channel_id,
+            self.relays,
+            self.volume,
+            self.volume_in,
+            self.volume_out,
+            self.fees,
+            self.fee_ppm
+        )
+    }
+}
+
+/// Screens of the app
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tab {
+    Dashboard,
+    Channels,
+    Peers,
+    Onchain,
+    Routing,
+    Hosted,
+    Fiat,
+    Log,
+    Income,
+}
+
+impl Tab {
+    /// All tabs in order of appearance
+    pub const ALL: [Tab; 9] = [
+        Tab::Dashboard,
+        Tab::Channels,
+        Tab::Peers,
+        Tab::Onchain,
+        Tab::Routing,
+        Tab::Hosted,
+        Tab::Fiat,
+        Tab::Log,
+        Tab::Income,
+    ];
+
+    /// Title of the tab, its first letter is the hotkey
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Dashboard => "Dashboard",
+            Tab::Channels => "Channels",
+            Tab::Peers => "Peers",
+            Tab::Onchain => "Onchain",
+            Tab::Routing => "Routing",
+            Tab::Hosted => "Hosted",
+            Tab::Fiat => "Fiat",
+            Tab::Log => "Log",
+            Tab::Income => "Income",
+        }
+    }
+
+    /// Lowercase first letter of the title
+    pub fn hotkey(self) -> char {
+        self.title().chars().next().unwrap_or_default().to_ascii_lowercase()
+    }
+
+    pub fn from_key(k: KeyCode) -> Option<Tab> {
+        match k {
+            KeyCode::Char(c) => Tab::ALL.iter().copied().find(|t| t.hotkey() == c),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Tab {
+    type Err = String;
+
+    /// Parse the tab title, ignoring the case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tab::ALL
+            .iter()
+            .copied()
+            .find(|t| t.title().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let titles: Vec<&str> = Tab::ALL.iter().map(|t| t.title()).collect();
+                format!("expected one of {}, got `{}`", titles.join(", "), s)
+            })
+    }
+}
+
+/// Read the map of node ids to labels that the operator gives to peers
+fn load_alias_map(path: &str) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// FNV-1a hash, stable across runs unlike the std hasher, so masked labels stay the same
+/// between screenshots
+fn stable_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Round to two significant digits, e.g. `1,234,567` to `1,200,000`
+fn round_significant(value: u64) -> u64 {
+    let mut scale = 1;
+    while value / scale >= 100 {
+        scale *= 10;
+    }
+    (value + scale / 2) / scale * scale
+}
+
+/// Pending modification of a hosted or fiat channel that the operator may need to accept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Proposal {
+    Resize { new_capacity: u64 },
+    Override { local_balance: u64, rate: Option<u64> },
+    Margin { new_capacity: u64, new_rate: u64 },
+}
+
+impl Proposal {
+    pub fn from_hosted(chan: &HostedChannel) -> Vec<Proposal> {
+        let data = &chan.data;
+        Proposal::from_parts(
+            data.resize_proposal.as_ref(),
+            data.override_proposal
+                .as_ref()
+                .map(|p| (p.local_balance_msat, None)),
+            data.margin_proposal.as_ref(),
+        )
+    }
+
+    pub fn from_fiat(chan: &FiatChannel) -> Vec<Proposal> {
+        let data = &chan.data;
+        Proposal::from_parts(
+            data.resize_proposal.as_ref(),
+            data.override_proposal
+                .as_ref()
+                .map(|p| (p.local_balance_msat, Some(p.rate))),
+            data.margin_proposal.as_ref(),
+        )
+    }
+
+    /// Proposals that hosted and fiat channels have in common, they differ only by the rate
+    /// of the override proposal that is given with the local balance
+    fn from_parts(
+        resize: Option<&ResizeProposal>,
+        override_balance: Option<(u64, Option<u64>)>,
+        margin: Option<&MarginProposal>,
+    ) -> Vec<Proposal> {
+        let mut res = vec![];
+        if let Some(p) = resize {
+            res.push(Proposal::Resize {
+                new_capacity: p.new_capacity,
+            });
+        }
+        if let Some((local_balance, rate)) = override_balance {
+            res.push(Proposal::Override {
+                local_balance,
+                rate,
+            });
+        }
+        if let Some(p) = margin {
+            res.push(Proposal::Margin {
+                new_capacity: p.new_capacity,
+                new_rate: p.new_rate,
+            });
+        }
+        res
+    }
+}
+
+impl std::fmt::Display for Proposal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Proposal::Resize { new_capacity } => write!(
+                f,
+                "Resize: cap {} sats",
+                (new_capacity / 1000).to_formatted_string(&Locale::en)
+            ),
+            Proposal::Override {
+                local_balance,
+                rate,
+            } => {
+                write!(
+                    f,
+                    "Override: local {} sats",
+                    (local_balance / 1000).to_formatted_string(&Locale::en)
+                )?;
+                match rate {
+                    Some(rate) => write!(f, ", rate {} sats", rate / 1000),
+                    None => Ok(()),
+                }
+            }
+            Proposal::Margin {
+                new_capacity,
+                new_rate,
+            } => write!(
+                f,
+                "Margin: {} sats, cap {}",
+                new_rate / 1000,
+                (new_capacity / 1000).to_formatted_string(&Locale::en)
+            ),
+        }
+    }
+}
+
+/// State of connection to the node that we show in the status bar
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConnectionStatus {
+    /// No poll is finished yet
+    Unknown,
+    Online,
+    /// The node responds too slowly, we keep trying
+    Retrying,
+    AuthFailed,
+    Unreachable,
+    /// The node responds with errors or data that we cannot parse
+    BadResponse,
+    /// The user asked to reconnect and the retry is in progress
+    Reconnecting,
+}
+
+impl ConnectionStatus {
+    pub fn from_error(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Timeout => ConnectionStatus::Retrying,
+            ErrorKind::Connect => ConnectionStatus::Unreachable,
+            ErrorKind::Unauthorized => ConnectionStatus::AuthFailed,
+            ErrorKind::NotFound | ErrorKind::Decode | ErrorKind::Server | ErrorKind::Other => {
+                ConnectionStatus::BadResponse
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConnectionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConnectionStatus::Unknown => write!(f, "connecting"),
+            ConnectionStatus::Online => write!(f, "online"),
+            ConnectionStatus::Retrying => write!(f, "timeout, retrying"),
+            ConnectionStatus::AuthFailed => write!(f, "authorization failed"),
+            ConnectionStatus::Unreachable => write!(f, "node unreachable"),
+            ConnectionStatus::BadResponse => write!(f, "bad response"),
+            ConnectionStatus::Reconnecting => write!(f, "reconnecting"),
+        }
+    }
+}
+
+/// Distribution of channels capacity in msat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityStats {
+    pub mean: u64,
+    pub median: u64,
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Payment received by the node, summed across all its parts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedPayment {
+    pub payment_hash: String,
+    pub amount: u64,
+    /// Time of the last received part
+    pub timestamp: u64,
+    /// Aliases of channels the parts came from
+    pub sources: Vec<String>,
+    /// Description of the invoice if we got it from the node
+    pub description: Option<String>,
+    pub status: Option<IncomingStatus>,
+}
+
+/// Totals of relays over the last day and month, amounts in msat
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RelayTotals {
+    pub count_day: u64,
+    pub count_month: u64,
+    pub relayed_day: u64,
+    pub relayed_month: u64,
+    pub fee_day: u64,
+    pub fee_month: u64,
+}
+
+/// Relays accumulated over all runs, amounts in msat. Persisted, as the audit that we
+/// fetch covers only a recent window.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RelayLifetime {
+    pub count: u64,
+    pub volume: u64,
+}
+
+/// Error that we keep in the log tab after the popup is dismissed
+#[derive(Debug, Clone)]
+pub struct ErrorEntry {
+    pub timestamp: i64,
+    pub message: String,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
@@ -77,13 +768,16 @@ pub enum ChannelType {
     Normal,
     Hosted,
     HostedFiat,
+    Unclassified,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum ChannelExt {
     Normal,
     Hosted,
     HostedFiat(FiatChannelData),
+    /// Channel without data while the hosted plugins are unknown or fail
+    Unclassified,
 }
 
 impl ChannelExt {
@@ -92,23 +786,46 @@ impl ChannelExt {
             ChannelExt::Normal => ChannelType::Normal,
             ChannelExt::Hosted => ChannelType::Hosted,
             ChannelExt::HostedFiat(_) => ChannelType::HostedFiat,
+            ChannelExt::Unclassified => ChannelType::Unclassified,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FiatChannelData {
     pub rate: u64,
+    /// Balance of the client valued by the rate source chosen with `--fiat-valuation`
     pub fiat_balance: f64,
+    /// Balance of the client valued at the rate of the channel
+    pub intrinsic_balance: f64,
+    /// Balance of the client valued at the BTC price of the oracle, if it is known for the
+    /// currency of the channel
+    pub market_balance: Option<f64>,
+    /// Ticker of the fiat currency, e.g. `EUR`
+    pub currency: String,
 }
 
 impl FiatChannelData {
+    /// Currency of fiat channels whose plugin doesn't tell it
+    pub const DEFAULT_CURRENCY: &'static str = "EUR";
+
     pub fn reverse_rate(&self) -> f64 {
         100_000_000_000.0 / self.rate as f64
     }
 }
 
-#[derive(Debug, Clone)]
+/// Breakdown of BTC exposure hedged by fiat channels, amounts are in msat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FiatExposure {
+    /// BTC that backs the fiat balances
+    pub hedged: u64,
+    /// Our own BTC in active channels whose value follows the BTC price
+    pub unhedged: u64,
+    /// Fraction of hedged BTC in all BTC that we are exposed to
+    pub hedge_ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ChannelStats {
     pub chan_state: ChannelState,
     pub node_id: String,
@@ -117,18 +834,66 @@ pub struct ChannelStats {
     pub local: u64,
     pub remote: u64,
     pub relays_amount: u64,
+    /// Relays that left through the channel, only they are charged by our fee policy
+    pub relays_out: u64,
     pub relays_volume: u64,
     pub relays_fees: u64,
     pub info_id: usize,
     pub public: bool,
+    pub feerate: Option<u64>,
     pub channel_ext: ChannelExt,
 }
 
+/// Stats of all channels with the same peer summed together
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PeerStats {
+    pub node_id: String,
+    pub alias: String,
+    pub channels: usize,
+    pub local: u64,
+    pub remote: u64,
+    pub relays_amount: u64,
+    pub relays_volume: u64,
+    pub relays_fees: u64,
+}
+
+impl PeerStats {
+    pub fn volume(&self) -> u64 {
+        self.local + self.remote
+    }
+}
+
 impl ChannelStats {
     pub fn volume(&self) -> u64 {
         self.local + self.remote
     }
 
+    /// Part of the capacity on the local side, or on the remote side if `remote` is set.
+    /// Zero for channels without capacity.
+    pub fn channel_ratio(&self, remote: bool) -> f64 {
+        let side = if remote { self.remote } else { self.local };
+        if self.volume() == 0 {
+            0.0
+        } else {
+            side as f64 / self.volume() as f64
+        }
+    }
+
+    /// Relayed volume over the stats interval per unit of capacity, shows how hard the
+    /// channel works regardless of its size
+    pub fn relay_velocity(&self) -> f64 {
+        if self.volume() == 0 {
+            0.0
+        } else {
+            self.relays_volume as f64 / self.volume() as f64
+        }
+    }
+
+    /// Channel without funds or without relays over the stats interval
+    pub fn is_idle(&self) -> bool {
+        self.volume() == 0 || self.relays_amount == 0
+    }
+
     pub fn is_normal_channel(&self) -> bool {
         self.channel_ext.channel_type() == ChannelType::Normal
     }
@@ -140,6 +905,22 @@ impl ChannelStats {
         }
     }
 
+    /// Fiat balance valued at the rate of the channel
+    pub fn intrinsic_balance(&self) -> f64 {
+        match &self.channel_ext {
+            ChannelExt::HostedFiat(data) => data.intrinsic_balance,
+            _ => 0.0,
+        }
+    }
+
+    /// Fiat balance valued at the BTC price of the oracle
+    pub fn market_balance(&self) -> Option<f64> {
+        match &self.channel_ext {
+            ChannelExt::HostedFiat(data) => data.market_balance,
+            _ => None,
+        }
+    }
+
     pub fn rate(&self) -> u64 {
         match &self.channel_ext {
             ChannelExt::HostedFiat(data) => data.rate,
@@ -153,33 +934,88 @@ impl ChannelStats {
             _ => 0.,
         }
     }
+
+    /// Fiat currency of the channel, empty for channels that are not fiat ones
+    pub fn currency(&self) -> &str {
+        match &self.channel_ext {
+            ChannelExt::HostedFiat(data) => &data.currency,
+            _ => "",
+        }
+    }
 }
 
 impl App {
-    pub async fn new(client: Client, db: sled::Db) -> Result<App, Box<dyn Error>> {
+    pub async fn new(
+        client: Client,
+        db: sled::Db,
+        opts: &Opts,
+        theme: Theme,
+    ) -> Result<App, Box<dyn Error>> {
         let node_info = client.get_info().await?;
-        let supported = client.get_supported_plugins().await?;
+        let client = client.with_quirks(Quirks::of(node_info.parsed_version()));
+        let version_warning = check_version(&node_info);
+        if let Some(warning) = &version_warning {
+            warn!("{}", warning);
+        }
+        let plugins = client.get_supported_plugins().await;
 
-        Ok(App {
+        let mut app = App {
             client,
             db,
-            tabs: vec![
-                "Dashboard".to_owned(),
-                "Channels".to_owned(),
-                "Peers".to_owned(),
-                "Onchain".to_owned(),
-                "Routing".to_owned(),
-                "Hosted".to_owned(),
-                "Fiat".to_owned(),
-            ],
+            tabs: vec![],
             tab_index: 0,
+            hide_hosted: opts.hide_hosted,
+            hide_fiat: opts.hide_fiat,
             errors: vec![],
-            supported,
+            max_errors: opts.max_errors.max(1),
+            errors_dropped: 0,
+            errors_timeout: opts.errors_timeout.filter(|t| *t > 0).map(i64::from),
+            errors_shown_at: 0,
+            balance_flashes: HashMap::new(),
+            errors_cooldown: opts.errors_cooldown,
+            dismissed_errors: HashMap::new(),
+            error_log: vec![],
+            connection: ConnectionStatus::Unknown,
+            breaker_open: false,
+            next_retry: None,
+            reconnect_requested: false,
+            refresh: Arc::new(Notify::new()),
+            refresh_on_focus: opts.refresh_on_focus,
+            debug_panic: opts.debug_panic,
+            commands_enabled: opts.enable_commands && cfg!(feature = "commands"),
+            confirm_command: None,
+            queued_command: None,
+            queued_route: None,
+            command_result: None,
+            supported: plugins.supported,
+            unknown_plugins: plugins.unknown,
+            plugin_errors: plugins.erroring,
+            warned_dataless: HashSet::new(),
             stats_interval: 24 * 3600,
+            max_fee_ratio: opts.max_fee_ratio,
+            price_source: opts
+                .price_url
+                .as_ref()
+                .map(|url| PriceSource::new(url, &opts.price_pointer)),
+            price_currency: opts.price_currency.clone(),
+            fiat_decimals: opts.fiat_decimals,
+            fiat_valuation: opts.fiat_valuation,
+            btc_price: None,
+            reserve_buffer: opts.reserve_buffer * 1000,
+            zombie_days: opts.zombie_days,
+            stuck_hours: opts.stuck_hours,
+            state_history: HashMap::new(),
+            sleeping_since: HashMap::new(),
+            leaderboard_path: opts.leaderboard_path.clone(),
+            stats_fifo: opts.stats_fifo.as_deref().map(StatsFifo::spawn),
+            pinned: HashSet::new(),
             node_info,
+            version_warning,
+            last_update: None,
             active_chans: 0,
             pending_chans: 0,
             sleeping_chans: 0,
+            closed_chans: 0,
             active_sats: 0,
             pending_sats: 0,
             sleeping_sats: 0,
@@ -189,97 +1025,994 @@ impl App {
             relayed_day: 0,
             fee_month: 0,
             fee_day: 0,
+            fees_paid_month: 0,
+            relays_lifetime: RelayLifetime::default(),
+            relays_pruned: 0,
+            stats_period: StatsPeriod::Month,
+            relayed_count_period: 0,
+            relayed_period: 0,
+            fee_period: 0,
+            audit_fetched_period: 0,
+            audit_full_at: 0,
+            audit_fetched_at: 0,
             return_rate: 0.0,
+            return_rate_provisional: true,
+            hosted_totals: RelayTotals::default(),
+            median_feerate: None,
+            channel_anomalies: vec![],
             screen_width: 80,
+            screen_height: 24,
+            compact_rows: std::cell::Cell::new(1),
             relays_maximum_volume: 0,
             relays_maximum_count: 0,
             relays_amounts_line: vec![],
             relays_volumes_line: vec![],
+            fiat_relays_maximum_volume: 0,
+            fiat_relays_maximum_count: 0,
+            fiat_relays_amounts_line: vec![],
+            fiat_relays_volumes_line: vec![],
+            hosted_relays_maximum_volume: 0,
+            hosted_relays_maximum_count: 0,
+            hosted_relays_amounts_line: vec![],
+            hosted_relays_volumes_line: vec![],
+            volumes_log_scale: false,
+            relays_hourly_counts: vec![],
+            sparkline_buckets: opts.sparkline_buckets,
+            daily_fees: vec![],
+            relays_hourly_volumes: vec![],
             channels_stats: vec![],
             hosted_stats: vec![],
             fiat_stats: vec![],
+            fiat_rate_deltas: HashMap::new(),
+            channel_volumes: HashMap::new(),
+            channel_fees: HashMap::new(),
             channels: vec![],
+            peers: vec![],
             audit: AuditInfo::default(),
             known_nodes: HashMap::new(),
+            incoming_payments: HashMap::new(),
+            incoming_queried: HashMap::new(),
+            check_reachability: opts.check_reachability,
+            reachability: HashMap::new(),
+            alias_map: match &opts.alias_map {
+                Some(path) => load_alias_map(path)
+                    .map_err(|e| format!("Failed to load alias map {}: {}", path, e))?,
+                None => HashMap::new(),
+            },
+            nodes_fetched: HashMap::new(),
             hc_channels: HashMap::new(),
             fc_channels: HashMap::new(),
+            hide_idle: false,
+            gauge_remote: false,
+            amount_unit: AmountUnit::Sat,
+            abbreviate_amounts: !opts.full_amounts,
+            anonymize: opts.anonymize,
+            anonymize_hide_balances: opts.anonymize_hide_balances,
+            dashboard_compact: false,
+            chans_sort: ChannelSort::Capacity,
+            timezone: opts.timezone,
+            theme,
             search_focused: false,
+            prompt: Prompt::JumpToPeer,
             search_line: "".to_owned(),
             channels_page: 0,
             chans_tab: 0,
-        })
+            chans_selected: 0,
+            chans_selected_id: None,
+            detail_open: false,
+            chans_grouped: false,
+            peers_selected: 0,
+            peers_sort: PeerSort::Node,
+            error_log_scroll: 0,
+            features_open: false,
+        };
+        app.build_tabs();
+        if app.fiat_valuation == FiatValuation::Oracle && app.price_source.is_none() {
+            return Err("--fiat-valuation oracle needs the BTC price from --price-url".into());
+        }
+        if let Some(tab) = opts.start_tab {
+            app.tab_index = app.tabs.iter().position(|t| *t == tab).ok_or_else(|| {
+                format!(
+                    "Tab {} is not available, the node lacks its plugin or the tab is hidden",
+                    tab.title()
+                )
+            })?;
+        }
+        if let Err(e) = app.migrate_state_since() {
+            warn!("Failed to migrate channel states: {}", e);
+        }
+        match app.load_pinned() {
+            Ok(pinned) => app.pinned = pinned,
+            Err(e) => warn!("Failed to load pinned channels: {}", e),
+        }
+        match app.load_relays_lifetime() {
+            Ok(lifetime) => app.relays_lifetime = lifetime,
+            Err(e) => warn!("Failed to load all-time relays: {}", e),
+        }
+        match app.load_audit() {
+            Ok(()) => app.update_relays_stats(),
+            Err(e) => warn!("Failed to load persisted audit: {}", e),
+        }
+        Ok(app)
     }
 
-    pub fn next_tab(&mut self) {
-        self.tab_index = (self.tab_index + 1) % self.tabs.len();
+    pub fn snapshot(&self) -> AppSnapshot<'_> {
+        AppSnapshot {
+            node_info: &self.node_info,
+            supported: self.supported.iter().map(|p| p.to_string()).sorted().collect(),
+            stats_interval: self.stats_interval,
+            channels: &self.channels,
+            peers: &self.peers,
+            hc_channels: &self.hc_channels,
+            fc_channels: &self.fc_channels,
+            audit: &self.audit,
+            known_nodes: &self.known_nodes,
+            channels_stats: &self.channels_stats,
+            hosted_stats: &self.hosted_stats,
+            fiat_stats: &self.fiat_stats,
+            pending_proposals: self.pending_proposals(),
+            active_sats: self.active_sats,
+            pending_sats: self.pending_sats,
+            sleeping_sats: self.sleeping_sats,
+            relayed_count_month: self.relayed_count_month,
+            relayed_count_day: self.relayed_count_day,
+            relayed_month: self.relayed_month,
+            relayed_day: self.relayed_day,
+            fee_month: self.fee_month,
+            fee_day: self.fee_day,
+            fees_paid_month: self.fees_paid_month,
+            return_rate: self.return_rate,
+            hosted_totals: &self.hosted_totals,
+        }
     }
 
-    pub fn previous_tab(&mut self) {
-        if self.tab_index > 0 {
-            self.tab_index -= 1;
+    /// Channels of the current sub tab with routing stats over the stats interval, ranked
+    /// by the sort order of the Channels screen. Pins only lift channels on the screen,
+    /// they don't change the rank. All sums of a row come from the same deduped relays.
+    pub fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        self.iterate_chans_tab()
+            .filter(|c| !self.is_hidden(c))
+            .sorted_by(|a, b| self.chans_order(a, b))
+            .enumerate()
+            .map(|(i, chan)| {
+                let relays: Vec<&RelayedInfo> = self
+                    .iterate_relays(self.stats_interval)
+                    .filter(|r| {
+                        r.from_channel_id == chan.chan_id || r.to_channel_id == chan.chan_id
+                    })
+                    .collect();
+                let sums = RelaySums::of(relays.iter().copied());
+                let volume_in = saturating_sum(
+                    relays
+                        .iter()
+                        .filter(|r| r.from_channel_id == chan.chan_id)
+                        .map(|r| r.amount_in),
+                );
+                let volume_out = saturating_sum(
+                    relays
+                        .iter()
+                        .filter(|r| r.to_channel_id == chan.chan_id)
+                        .map(|r| r.amount_out),
+                );
+                let fee_ppm = if sums.volume == 0 {
+                    0
+                } else {
+                    (sums.fee as u128 * 1_000_000 / sums.volume as u128) as u64
+                };
+                LeaderboardEntry {
+                    rank: i + 1,
+                    alias: chan.alias.clone(),
+                    channel_id: chan.chan_id.clone(),
+                    relays: sums.count,
+                    volume: sums.volume,
+                    volume_in,
+                    volume_out,
+                    fees: sums.fee,
+                    fee_ppm,
+                }
+            })
+            .collect()
+    }
+
+    /// Write the leaderboard to the file as JSON or CSV depending on the extension
+    pub fn export_leaderboard(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let entries = self.leaderboard();
+        let content = if path.ends_with(".json") {
+            serde_json::to_string_pretty(&entries)?
         } else {
-            self.tab_index = self.tabs.len() - 1;
-        }
+            iter::once(LeaderboardEntry::CSV_HEADER.to_owned())
+                .chain(entries.iter().map(|e| e.to_csv()))
+                .join("\n")
+        };
+        std::fs::write(path, content)?;
+        Ok(())
     }
 
-    pub fn react_hotkey(&mut self, k: KeyCode) {
-        if self.tab_index == 0 || self.tab_index == 5 || self.tab_index == 6 {
-            match k {
-                KeyCode::Up => {
-                    self.channels_page = if self.channels_page == 0 {
-                        0
-                    } else {
-                        self.channels_page - 1
-                    }
-                }
-                KeyCode::Down => self.channels_page += 1,
-                _ => (),
-            }
-        } else if self.tab_index == 1 {
-            match k {
-                KeyCode::Char('a') => self.chans_tab = 0,
-                KeyCode::Char('e') => self.chans_tab = 1,
-                KeyCode::Char('s') => self.chans_tab = 2,
-                _ => (),
+    fn export_leaderboard_reporting(&mut self) {
+        let path = self.leaderboard_path.clone();
+        match self.export_leaderboard(&path) {
+            Ok(()) => {
+                info!("Leaderboard written to {}", path);
+                self.command_result = Some(format!("Leaderboard written to {}", path));
             }
-        }
-
-        match k {
-            KeyCode::Char('d') => self.tab_index = 0,
-            KeyCode::Char('c') => self.tab_index = 1,
-            KeyCode::Char('p') => self.tab_index = 2,
-            KeyCode::Char('o') => self.tab_index = 3,
-            KeyCode::Char('r') => self.tab_index = 4,
-            KeyCode::Char('h') => self.tab_index = 5,
-            KeyCode::Char('f') => self.tab_index = 6,
-            _ => (),
+            Err(e) => self.push_error(format!("Failed to write leaderboard to {}: {}", path, e)),
         }
     }
 
-    pub fn get_active_chans(&self) -> usize {
-        self.iterate_active_chans().count()
+    pub fn dashboard_snapshot(&self) -> DashboardSnapshot<'_> {
+        let (pending_htlcs, pending_htlcs_amount) = self.pending_htlcs();
+        DashboardSnapshot {
+            timestamp: self.last_update,
+            node_id: &self.node_info.node_id,
+            alias: &self.node_info.alias,
+            block_height: self.node_info.block_height,
+            active_chans: self.active_chans,
+            pending_chans: self.pending_chans,
+            sleeping_chans: self.sleeping_chans,
+            active_sats: self.active_sats,
+            pending_sats: self.pending_sats,
+            sleeping_sats: self.sleeping_sats,
+            pending_htlcs,
+            pending_htlcs_amount,
+            relayed_count_month: self.relayed_count_month,
+            relayed_count_day: self.relayed_count_day,
+            relayed_month: self.relayed_month,
+            relayed_day: self.relayed_day,
+            fee_month: self.fee_month,
+            fee_day: self.fee_day,
+            fees_paid_month: self.fees_paid_month,
+            relays_lifetime: self.relays_lifetime,
+            return_rate: self.return_rate,
+            return_rate_provisional: self.return_rate_provisional,
+            btc_price: self.btc_price,
+        }
     }
 
-    pub fn iterate_active_chans(&self) -> impl Iterator<Item = &ChannelInfo> {
-        self.channels.iter().filter(|c| c.state.is_normal())
+    /// Send the Dashboard numbers to the `--stats-fifo` reader, if any
+    fn publish_stats(&self) {
+        if let Some(fifo) = &self.stats_fifo {
+            match serde_json::to_string(&self.dashboard_snapshot()) {
+                Ok(line) => fifo.publish(line),
+                Err(e) => warn!("Failed to serialize dashboard stats: {}", e),
+            }
+        }
     }
 
-    pub fn get_pending_chans(&self) -> usize {
-        self.iterate_pending_chans().count()
+    /// Write the snapshot of the state as JSON to the file
+    pub fn dump_state(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(&self.snapshot())?;
+        std::fs::write(path, json)?;
+        Ok(())
     }
 
-    pub fn iterate_pending_chans(&self) -> impl Iterator<Item = &ChannelInfo> {
-        self.channels.iter().filter(|c| c.state.is_pending())
+    /// Tab that is currently shown
+    pub fn current_tab(&self) -> Tab {
+        self.tabs[self.tab_index]
     }
 
-    pub fn get_sleeping_chans(&self) -> usize {
-        self.iterate_sleeping_chans().count()
+    /// Switch to the tab if it is shown
+    pub fn select_tab(&mut self, tab: Tab) {
+        if let Some(i) = self.tabs.iter().position(|t| *t == tab) {
+            self.tab_index = i;
+        }
+    }
+
+    /// Collect shown tabs from supported plugins and visibility preferences. Keeps the current
+    /// tab selected if it is still shown.
+    pub fn build_tabs(&mut self) {
+        let current = self.tabs.get(self.tab_index).copied();
+        self.tabs = Tab::ALL
+            .iter()
+            .copied()
+            .filter(|tab| match tab {
+                Tab::Hosted => {
+                    !self.hide_hosted && self.supported.contains(&NodePlugin::HostedChannels)
+                }
+                Tab::Fiat => !self.hide_fiat && self.supported.contains(&NodePlugin::FiatChannels),
+                _ => true,
+            })
+            .collect();
+        self.tab_index = current
+            .and_then(|tab| self.tabs.iter().position(|t| *t == tab))
+            .unwrap_or(0);
+    }
+
+    pub fn next_tab(&mut self) {
+        self.tab_index = (self.tab_index + 1) % self.tabs.len();
+    }
+
+    pub fn previous_tab(&mut self) {
+        if self.tab_index > 0 {
+            self.tab_index -= 1;
+        } else {
+            self.tab_index = self.tabs.len() - 1;
+        }
+    }
+
+    pub fn react_hotkey(&mut self, k: KeyCode) {
+        if self.search_focused {
+            self.react_search(k);
+            return;
+        }
+        if self.confirm_command.is_some() {
+            match k {
+                KeyCode::Char('y') => self.queued_command = self.confirm_command.take(),
+                KeyCode::Char('n') => self.confirm_command = None,
+                _ => (),
+            }
+            return;
+        }
+        if k == KeyCode::Enter && self.command_result.is_some() {
+            self.command_result = None;
+            return;
+        }
+
+        let tab = self.current_tab();
+        if tab == Tab::Dashboard || tab == Tab::Hosted || tab == Tab::Fiat {
+            let last_page = self.get_channels_pages(tab) - 1;
+            match k {
+                KeyCode::Up | KeyCode::PageUp => {
+                    self.channels_page = self.channels_page.saturating_sub(1)
+                }
+                KeyCode::Down | KeyCode::PageDown => {
+                    self.channels_page = (self.channels_page + 1).min(last_page)
+                }
+                KeyCode::Home => self.channels_page = 0,
+                KeyCode::End => self.channels_page = last_page,
+                _ => (),
+            }
+        } else if tab == Tab::Channels && self.detail_open {
+            match k {
+                KeyCode::Enter => self.detail_open = false,
+                KeyCode::Char('x') => self.confirm_close_selected(false),
+                KeyCode::Char('X') => self.confirm_close_selected(true),
+                _ => (),
+            }
+        } else if tab == Tab::Channels {
+            match k {
+                KeyCode::Char('a') => self.select_chans_tab(0),
+                KeyCode::Char('e') => self.select_chans_tab(1),
+                KeyCode::Char('s') => self.select_chans_tab(2),
+                KeyCode::Char('C') => self.select_chans_tab(3),
+                KeyCode::Up => self.select_chan_row(self.chans_selected_index().saturating_sub(1)),
+                KeyCode::Down => {
+                    let i = self.chans_selected_index();
+                    if i + 1 < self.get_chans_list_len() {
+                        self.select_chan_row(i + 1);
+                    }
+                }
+                KeyCode::Enter if !self.chans_grouped => {
+                    self.detail_open = self.get_selected_channel().is_some()
+                }
+                KeyCode::Char('g') => {
+                    self.chans_grouped = !self.chans_grouped;
+                    self.select_chan_row(0);
+                }
+                KeyCode::Char('P') if !self.chans_grouped => self.toggle_pin_selected(),
+                KeyCode::Char('w') => self.export_leaderboard_reporting(),
+                KeyCode::Char('t') => {
+                    self.chans_sort = match self.chans_sort {
+                        ChannelSort::Capacity => ChannelSort::Velocity,
+                        ChannelSort::Velocity => ChannelSort::Capacity,
+                    };
+                    self.select_chan_row(0);
+                }
+                _ => (),
+            }
+        } else if tab == Tab::Peers {
+            match k {
+                KeyCode::Up => self.peers_selected = self.peers_selected.saturating_sub(1),
+                KeyCode::Down if self.peers_selected + 1 < self.peers.len() => {
+                    self.peers_selected += 1;
+                }
+                KeyCode::Char('t') => {
+                    self.peers_sort = match self.peers_sort {
+                        PeerSort::Node => PeerSort::LastRelay,
+                        PeerSort::LastRelay => PeerSort::Node,
+                    };
+                    self.peers_selected = 0;
+                }
+                KeyCode::Char('C') => self.confirm_connect_selected(),
+                KeyCode::Char('D') => self.confirm_disconnect_selected(),
+                _ => (),
+            }
+        } else if tab == Tab::Log {
+            match k {
+                KeyCode::Up => self.error_log_scroll = self.error_log_scroll.saturating_sub(1),
+                KeyCode::Down if self.error_log_scroll + 1 < self.error_log.len() => {
+                    self.error_log_scroll += 1;
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(tab) = Tab::from_key(k) {
+            self.select_tab(tab);
+        }
+        match k {
+            KeyCode::Char('H') => {
+                self.hide_hosted = !self.hide_hosted;
+                self.build_tabs();
+            }
+            KeyCode::Char('F') => {
+                self.hide_fiat = !self.hide_fiat;
+                self.build_tabs();
+            }
+            KeyCode::Char('z') => {
+                self.hide_idle = !self.hide_idle;
+                self.select_chan_row(0);
+            }
+            KeyCode::Char('n') => self.features_open = !self.features_open,
+            KeyCode::Char('/') => self.open_prompt(Prompt::JumpToPeer),
+            KeyCode::Char('R') if self.commands_enabled => self.open_prompt(Prompt::FindRoute),
+            KeyCode::Char('b') => self.gauge_remote = !self.gauge_remote,
+            KeyCode::Char('u') => self.amount_unit = self.amount_unit.next(),
+            KeyCode::Char('A') => self.anonymize = !self.anonymize,
+            KeyCode::F(5) => self.request_reconnect(),
+            KeyCode::Char('t') if tab == Tab::Dashboard => {
+                self.stats_period = self.stats_period.next();
+                self.update_period_stats();
+            }
+            KeyCode::Char('m') if tab == Tab::Dashboard => {
+                self.dashboard_compact = !self.dashboard_compact;
+                self.channels_page = 0;
+            }
+            KeyCode::Char('v') => {
+                self.volumes_log_scale = !self.volumes_log_scale;
+                self.update_relays_lines();
+            }
+            _ => (),
+        }
+    }
+
+    /// Retry the node right away instead of waiting for the next poll or the backoff
+    pub fn request_reconnect(&mut self) {
+        if self.reconnect_requested {
+            return;
+        }
+        info!("Reconnect is requested");
+        self.reconnect_requested = true;
+        self.connection = ConnectionStatus::Reconnecting;
+        self.next_retry = None;
+        self.refresh.notify_one();
+    }
+
+    /// Close the topmost popup, returns false if there is nothing to close
+    pub fn close_popup(&mut self) -> bool {
+        if self.search_focused {
+            self.search_focused = false;
+        } else if self.confirm_command.is_some() {
+            self.confirm_command = None;
+        } else if self.command_result.is_some() {
+            self.command_result = None;
+        } else if self.features_open {
+            self.features_open = false;
+        } else if self.detail_open {
+            self.detail_open = false;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Switch to the screen with the first channel whose peer alias contains the query
+    /// (or whose node id starts with it) and select the channel. Normal channels are
+    /// matched first, then hosted and fiat ones.
+    pub fn jump_to_peer(&mut self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return false;
+        }
+        let matches = |c: &ChannelStats| {
+            c.alias.to_lowercase().contains(&query) || c.node_id.starts_with(&query)
+        };
+        // Idle channels are hidden, so we look for the channel with all channels shown
+        let hide_idle = self.hide_idle;
+        self.hide_idle = false;
+
+        if let Some(chan) = self.channels_stats.iter().find(|c| matches(c)) {
+            let chan_id = chan.chan_id.clone();
+            self.chans_tab = chans_tab_of(self.channels[chan.info_id].state);
+            self.chans_grouped = false;
+            self.detail_open = false;
+            self.hide_idle = hide_idle && !chan.is_idle();
+            let row = self
+                .get_chans_tab_stats()
+                .iter()
+                .position(|c| c.chan_id == chan_id)
+                .unwrap_or(0);
+            self.select_chan_row(row);
+            self.select_tab(Tab::Channels);
+            return true;
+        }
+        for tab in [Tab::Hosted, Tab::Fiat] {
+            if !self.tabs.contains(&tab) {
+                continue;
+            }
+            let stats = if tab == Tab::Hosted {
+                &self.hosted_stats
+            } else {
+                &self.fiat_stats
+            };
+            let (chan_id, idle) = match stats.iter().find(|c| matches(c)) {
+                Some(chan) => (chan.chan_id.clone(), chan.is_idle()),
+                None => continue,
+            };
+            self.hide_idle = hide_idle && !idle;
+            let (shown, page_size) = if tab == Tab::Hosted {
+                (self.get_hosted_tab_stats(), App::CHANNELS_PAGE_SIZE)
+            } else {
+                (self.get_fiat_tab_stats(), App::FIAT_PAGE_SIZE)
+            };
+            let position = shown.iter().position(|c| c.chan_id == chan_id).unwrap_or(0);
+            self.select_tab(tab);
+            self.channels_page = (position / page_size) as u64;
+            return true;
+        }
+        self.hide_idle = hide_idle;
+        false
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt) {
+        self.search_focused = true;
+        self.prompt = prompt;
+        self.search_line.clear();
+    }
+
+    fn react_search(&mut self, k: KeyCode) {
+        match k {
+            KeyCode::Char(c) => self.search_line.push(c),
+            KeyCode::Backspace => {
+                self.search_line.pop();
+            }
+            KeyCode::Enter => {
+                self.search_focused = false;
+                let query = self.search_line.clone();
+                match self.prompt {
+                    Prompt::JumpToPeer => {
+                        if !self.jump_to_peer(&query) {
+                            self.command_result = Some(format!("No channel with peer matching '{}'", query));
+                        }
+                    }
+                    Prompt::FindRoute => match query.parse() {
+                        Ok(route) => self.queued_route = Some(route),
+                        Err(e) => self.command_result = Some(e),
+                    },
+                }
+            }
+            KeyCode::Esc => self.search_focused = false,
+            _ => (),
+        }
+    }
+
+    fn select_chans_tab(&mut self, tab: usize) {
+        self.chans_tab = tab;
+        self.select_chan_row(0);
+    }
+
+    pub fn is_hidden(&self, chan: &ChannelStats) -> bool {
+        self.hide_idle && chan.is_idle()
+    }
+
+    fn iterate_chans_tab(&self) -> impl Iterator<Item = &ChannelStats> {
+        self.channels_stats.iter().filter(move |c| {
+            let state = self.channels[c.info_id].state;
+            match self.chans_tab {
+                0 => state.is_normal(),
+                1 => state.is_pending(),
+                2 => state.is_sleeping(),
+                _ => state.is_closed(),
+            }
+        })
+    }
+
+    /// Channels of the current sub tab on the Channels screen in the order we display them
+    pub fn get_chans_tab_stats(&self) -> Vec<&ChannelStats> {
+        let mut chans: Vec<&ChannelStats> = self
+            .iterate_chans_tab()
+            .filter(|c| !self.is_hidden(c))
+            .collect();
+        chans.sort_by(|a, b| self.pinned_first(a, b).then_with(|| self.chans_order(a, b)));
+        chans
+    }
+
+    /// Order of the Channels screen chosen with `t`, without pinned channels first
+    fn chans_order(&self, a: &ChannelStats, b: &ChannelStats) -> Ordering {
+        match self.chans_sort {
+            ChannelSort::Capacity => b.volume().cmp(&a.volume()),
+            ChannelSort::Velocity => b
+                .relay_velocity()
+                .partial_cmp(&a.relay_velocity())
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+
+    /// Hosted channels in the order that the Hosted screen shows them
+    pub fn get_hosted_tab_stats(&self) -> Vec<&ChannelStats> {
+        self.hosted_stats
+            .iter()
+            .filter(|c| !self.is_hidden(c))
+            .sorted_by(|a, b| {
+                self.pinned_first(a, b)
+                    .then_with(|| b.relays_volume.cmp(&a.relays_volume))
+            })
+            .collect()
+    }
+
+    /// Fiat channels in the order that the Fiat screen shows them
+    pub fn get_fiat_tab_stats(&self) -> Vec<&ChannelStats> {
+        self.fiat_stats
+            .iter()
+            .filter(|c| !self.is_hidden(c))
+            .sorted_by(|a, b| {
+                self.pinned_first(a, b)
+                    .then_with(|| a.currency().cmp(b.currency()))
+                    .then_with(|| b.fiat_balance().partial_cmp(&a.fiat_balance()).unwrap())
+            })
+            .collect()
+    }
+
+    /// Amount of channels of the current sub tab that are hidden as idle
+    pub fn get_chans_tab_hidden(&self) -> usize {
+        self.iterate_chans_tab().filter(|c| self.is_hidden(c)).count()
+    }
+
+    /// Channels of the current sub tab collapsed by peer, biggest peers first
+    pub fn grouped_by_peer(&self) -> Vec<PeerStats> {
+        let mut peers: HashMap<&str, PeerStats> = HashMap::new();
+        for c in self.get_chans_tab_stats() {
+            let peer = peers.entry(&c.node_id).or_insert_with(|| PeerStats {
+                node_id: c.node_id.clone(),
+                alias: c.alias.clone(),
+                channels: 0,
+                local: 0,
+                remote: 0,
+                relays_amount: 0,
+                relays_volume: 0,
+                relays_fees: 0,
+            });
+            peer.channels += 1;
+            peer.local = peer.local.saturating_add(c.local);
+            peer.remote = peer.remote.saturating_add(c.remote);
+            peer.relays_amount += c.relays_amount;
+            peer.relays_volume = peer.relays_volume.saturating_add(c.relays_volume);
+            peer.relays_fees = peer.relays_fees.saturating_add(c.relays_fees);
+        }
+        peers
+            .into_values()
+            .sorted_by(|a, b| {
+                b.volume()
+                    .cmp(&a.volume())
+                    .then_with(|| a.node_id.cmp(&b.node_id))
+            })
+            .collect()
+    }
+
+    /// Amount of rows in the channels list, either channels or grouped peers
+    fn get_chans_list_len(&self) -> usize {
+        if self.chans_grouped {
+            self.grouped_by_peer().len()
+        } else {
+            self.get_chans_tab_stats().len()
+        }
+    }
+
+    /// Put the cursor on the row of the channels list and remember its channel
+    fn select_chan_row(&mut self, row: usize) {
+        self.chans_selected = row;
+        self.chans_selected_id = if self.chans_grouped {
+            None
+        } else {
+            self.get_chans_tab_stats().get(row).map(|c| c.chan_id.clone())
+        };
+    }
+
+    /// Row of the selected channel in the current order of the list
+    pub fn chans_selected_index(&self) -> usize {
+        let tracked = self.chans_selected_id.as_ref().and_then(|id| {
+            self.get_chans_tab_stats()
+                .iter()
+                .position(|c| &c.chan_id == id)
+        });
+        match tracked {
+            Some(row) if !self.chans_grouped => row,
+            _ => self.chans_selected.min(self.get_chans_list_len().saturating_sub(1)),
+        }
+    }
+
+    /// Select the channel again after a poll when it is gone from the list, otherwise the
+    /// cursor points to a row without a channel to act on. The detail popup of the gone
+    /// channel is closed rather than switched to the neighbour.
+    fn resync_chans_selection(&mut self) {
+        if self.get_selected_channel().is_none() {
+            self.detail_open = false;
+            self.select_chan_row(self.chans_selected_index());
+        }
+    }
+
+    pub fn get_selected_channel(&self) -> Option<&ChannelStats> {
+        let id = self.chans_selected_id.as_ref()?;
+        self.get_chans_tab_stats().into_iter().find(|c| &c.chan_id == id)
+    }
+
+    fn confirm_close_selected(&mut self, force: bool) {
+        if !self.commands_enabled {
+            return;
+        }
+        if let Some(chan) = self.get_selected_channel() {
+            self.confirm_command = Some(Command::CloseChannel {
+                channel_id: chan.chan_id.clone(),
+                node_id: chan.node_id.clone(),
+                alias: chan.alias.clone(),
+                force,
+            });
+        }
+    }
+
+    /// How long we trust the cached node info before querying it again
+    const NODES_TTL: i64 = 3600;
+
+    /// Node info is missing or expired
+    fn is_node_stale(&self, node_id: &str, now: i64) -> bool {
+        self.nodes_fetched
+            .get(node_id)
+            .is_none_or(|fetched| now - fetched > App::NODES_TTL)
+    }
+
+    /// Label of the node from the alias map, otherwise its gossip alias if we know it, otherwise
+    /// its id
+    pub fn get_peer_alias(&self, node_id: &str) -> String {
+        if let Some(label) = self.alias_map.get(node_id) {
+            return label.clone();
+        }
+        self.known_nodes
+            .get(node_id)
+            .map(|n| n.alias.clone())
+            .unwrap_or_else(|| node_id.to_owned())
+    }
+
+    pub fn get_selected_peer(&self) -> Option<&PeerInfo> {
+        self.get_sorted_peers().get(self.peers_selected).copied()
+    }
+
+    /// Unix time of the latest relay through channels of each peer. Peers without relays in
+    /// the audit are missing.
+    pub fn peers_last_relay(&self) -> HashMap<&str, u64> {
+        let chan_peers: HashMap<&str, &str> = self
+            .channels_stats
+            .iter()
+            .chain(self.hosted_stats.iter())
+            .chain(self.fiat_stats.iter())
+            .map(|c| (c.chan_id.as_str(), c.node_id.as_str()))
+            .collect();
+        let mut last_relay: HashMap<&str, u64> = HashMap::new();
+        for relay in self.audit.relayed.iter() {
+            for chan_id in [&relay.from_channel_id, &relay.to_channel_id] {
+                if let Some(node_id) = chan_peers.get(chan_id.as_str()) {
+                    let time = last_relay.entry(node_id).or_insert(0);
+                    *time = (*time).max(relay.timestamp.unix);
+                }
+            }
+        }
+        last_relay
+    }
+
+    /// Peers in the order of the Peers screen
+    pub fn get_sorted_peers(&self) -> Vec<&PeerInfo> {
+        let mut peers: Vec<&PeerInfo> = self.peers.iter().collect();
+        if self.peers_sort == PeerSort::LastRelay {
+            let last_relay = self.peers_last_relay();
+            // Stable sort keeps the node order among peers without relays
+            peers.sort_by_key(|p| {
+                std::cmp::Reverse(last_relay.get(p.node_id.as_str()).copied().unwrap_or(0))
+            });
+        }
+        peers
+    }
+
+    /// Address of the peer to connect to: the current one or the first announced
+    fn get_peer_address(&self, peer: &PeerInfo) -> Option<String> {
+        peer.address.clone().or_else(|| {
+            self.known_nodes
+                .get(&peer.node_id)
+                .and_then(|n| n.addresses.first().cloned())
+        })
+    }
+
+    fn confirm_connect_selected(&mut self) {
+        if !self.commands_enabled {
+            return;
+        }
+        if let Some(peer) = self.get_selected_peer().cloned() {
+            let alias = self.get_peer_alias(&peer.node_id);
+            match self.get_peer_address(&peer) {
+                Some(address) => {
+                    self.confirm_command = Some(Command::ConnectPeer {
+                        uri: format!("{}@{}", peer.node_id, address),
+                        alias,
+                    })
+                }
+                None => self.push_error(format!("No known address of peer {}", alias)),
+            }
+        }
+    }
+
+    fn confirm_disconnect_selected(&mut self) {
+        if !self.commands_enabled {
+            return;
+        }
+        if let Some(peer) = self.get_selected_peer() {
+            self.confirm_command = Some(Command::DisconnectPeer {
+                node_id: peer.node_id.clone(),
+                alias: self.get_peer_alias(&peer.node_id),
+            });
+        }
+    }
+
+    /// Channel cards per page on the Dashboard and Hosted tabs, two columns of four
+    pub const CHANNELS_PAGE_SIZE: usize = 8;
+    /// Fiat channel cards per page, two columns of six
+    pub const FIAT_PAGE_SIZE: usize = 12;
+
+    fn count_normal_channels(&self) -> usize {
+        self.channels_stats.iter().filter(|c| c.is_normal_channel()).count()
+    }
+
+    /// Count of channel pages on the tab, at least one even if there are no channels
+    fn get_channels_pages(&self, tab: Tab) -> u64 {
+        let (count, page_size) = match tab {
+            Tab::Hosted => (
+                self.hosted_stats.iter().filter(|c| !self.is_hidden(c)).count(),
+                App::CHANNELS_PAGE_SIZE,
+            ),
+            Tab::Fiat => (
+                self.fiat_stats.iter().filter(|c| !self.is_hidden(c)).count(),
+                App::FIAT_PAGE_SIZE,
+            ),
+            // Only normal channels are drawn on the dashboard
+            Tab::Dashboard if self.dashboard_compact => {
+                (self.count_normal_channels(), self.compact_rows.get().max(1))
+            }
+            _ => (self.count_normal_channels(), App::CHANNELS_PAGE_SIZE),
+        };
+        count.div_ceil(page_size).max(1) as u64
+    }
+
+    /// How many errors we keep for the log tab, older ones are dropped from it
+    pub const ERROR_LOG_LIMIT: usize = 100;
+
+    /// Show error in the popup and keep it in the log tab. The popup isn't shown again for
+    /// the same error that is already there or was dismissed less than `errors_cooldown`
+    /// seconds ago, so messages must not contain varying parts like the current time.
+    pub fn push_error(&mut self, message: String) {
+        let now = chrono::offset::Utc::now().timestamp();
+        let cooling = self
+            .dismissed_errors
+            .get(&message)
+            .is_some_and(|dismissed| now - dismissed < self.errors_cooldown);
+        if cooling {
+            info!("Not showing recently dismissed error again: {}", message);
+        } else if !self.errors.contains(&message) {
+            self.errors.push(message.clone());
+            self.errors_shown_at = now;
+            if self.errors.len() > self.max_errors {
+                let excess = self.errors.len() - self.max_errors;
+                self.errors.drain(..excess);
+                self.errors_dropped += excess;
+            }
+        }
+        self.error_log.push(ErrorEntry {
+            timestamp: now,
+            message,
+        });
+        if self.error_log.len() > App::ERROR_LOG_LIMIT {
+            let excess = self.error_log.len() - App::ERROR_LOG_LIMIT;
+            self.error_log.drain(..excess);
+        }
+    }
+
+    /// Close the errors popup once it stayed unchanged for the timeout
+    pub fn tick_errors(&mut self) {
+        if self.errors.is_empty() {
+            return;
+        }
+        if let Some(timeout) = self.errors_timeout {
+            let now = chrono::offset::Utc::now().timestamp();
+            if now - self.errors_shown_at >= timeout {
+                self.dismiss_errors();
+            }
+        }
+    }
+
+    /// How many ticks a channel stays highlighted after its balance changed
+    const FLASH_TICKS: u32 = 3;
+
+    /// Highlight channels whose local balance changed since the previous poll
+    fn flash_balance_changes(&mut self, changed: Vec<String>) {
+        for chan_id in changed {
+            self.balance_flashes.insert(chan_id, App::FLASH_TICKS);
+        }
+    }
+
+    /// Count the tick for highlighted channels and stop highlighting them once it decays
+    pub fn tick_flashes(&mut self) {
+        self.balance_flashes.retain(|_, ticks| {
+            *ticks -= 1;
+            *ticks > 0
+        });
+    }
+
+    pub fn is_flashing(&self, chan: &ChannelStats) -> bool {
+        self.balance_flashes.contains_key(&chan.chan_id)
+    }
+
+    /// Close the errors popup and start the cooldown of the errors that were shown
+    pub fn dismiss_errors(&mut self) {
+        let now = chrono::offset::Utc::now().timestamp();
+        let cooldown = self.errors_cooldown;
+        self.dismissed_errors.retain(|_, dismissed| now - *dismissed < cooldown);
+        for message in self.errors.drain(..) {
+            self.dismissed_errors.insert(message, now);
+        }
+        self.errors_dropped = 0;
+    }
+
+    /// Dismiss only the topmost error of the popup, so the rest can be read through
+    pub fn dismiss_top_error(&mut self) {
+        if self.errors.is_empty() {
+            return;
+        }
+        let now = chrono::offset::Utc::now().timestamp();
+        let message = self.errors.remove(0);
+        self.dismissed_errors.insert(message, now);
+        self.errors_shown_at = now;
+        if self.errors.is_empty() {
+            self.errors_dropped = 0;
+        }
+    }
+
+    pub fn get_active_chans(&self) -> usize {
+        self.iterate_active_chans().count()
+    }
+
+    pub fn iterate_active_chans(&self) -> impl Iterator<Item = &ChannelInfo> {
+        self.channels.iter().filter(|c| c.state.is_normal())
+    }
+
+    pub fn get_pending_chans(&self) -> usize {
+        self.iterate_pending_chans().count()
+    }
+
+    pub fn iterate_pending_chans(&self) -> impl Iterator<Item = &ChannelInfo> {
+        self.channels.iter().filter(|c| c.state.is_pending())
+    }
+
+    pub fn get_sleeping_chans(&self) -> usize {
+        self.iterate_sleeping_chans().count()
     }
 
     pub fn iterate_sleeping_chans(&self) -> impl Iterator<Item = &ChannelInfo> {
         self.channels.iter().filter(|c| c.state.is_sleeping())
     }
 
+    /// Channels that earned the most fees over the stats interval, best first
+    pub fn get_top_fee_channels(&self, limit: usize) -> Vec<&ChannelStats> {
+        self.channels_stats
+            .iter()
+            .filter(|c| c.relays_fees > 0)
+            .sorted_by(|a, b| {
+                b.relays_fees
+                    .cmp(&a.relays_fees)
+                    .then_with(|| a.chan_id.cmp(&b.chan_id))
+            })
+            .take(limit)
+            .collect()
+    }
+
+    pub fn get_closed_chans(&self) -> usize {
+        self.channels.iter().filter(|c| c.state.is_closed()).count()
+    }
+
     pub fn get_active_fiat_chans(&self) -> usize {
         self.iterate_active_fiat_chans().count()
     }
@@ -322,132 +2055,1318 @@ impl App {
             .sum()
     }
 
-    pub fn get_pending_sats(&self) -> u64 {
+    pub fn get_pending_sats(&self) -> u64 {
+        self.channels
+            .iter()
+            .filter_map(|c| {
+                if c.state.is_pending() {
+                    c.data.as_ref()
+                } else {
+                    None
+                }
+            })
+            .map(|c| c.commitments.local_commit.spec.to_local)
+            .sum()
+    }
+
+    pub fn get_sleeping_sats(&self) -> u64 {
+        self.channels
+            .iter()
+            .filter_map(|c| {
+                if c.state.is_sleeping() {
+                    c.data.as_ref()
+                } else {
+                    None
+                }
+            })
+            .map(|c| c.commitments.local_commit.spec.to_local)
+            .sum()
+    }
+
+    /// Capacity distribution of active channels, None if there are no active channels
+    pub fn get_capacity_stats(&self) -> Option<CapacityStats> {
+        let mut capacities: Vec<u64> = self.iterate_active_chans().map(|c| c.volume()).collect();
+        if capacities.is_empty() {
+            return None;
+        }
+        capacities.sort_unstable();
+        let n = capacities.len();
+        let median = if n.is_multiple_of(2) {
+            (capacities[n / 2 - 1] + capacities[n / 2]) / 2
+        } else {
+            capacities[n / 2]
+        };
+        Some(CapacityStats {
+            mean: capacities.iter().sum::<u64>() / n as u64,
+            median,
+            min: capacities[0],
+            max: capacities[n - 1],
+        })
+    }
+
+    /// Amount of active channels in capacity ranges, in sats
+    pub fn capacity_histogram(&self) -> Vec<(String, u64)> {
+        const MSAT: u64 = 1000;
+        let ranges = [
+            ("<1M", 0, 1_000_000 * MSAT),
+            ("1-5M", 1_000_000 * MSAT, 5_000_000 * MSAT),
+            ("5-10M", 5_000_000 * MSAT, 10_000_000 * MSAT),
+            (">10M", 10_000_000 * MSAT, u64::MAX),
+        ];
+        let mut counts = [0u64; 4];
+        for chan in self.iterate_active_chans() {
+            let volume = chan.volume();
+            if let Some(i) = ranges
+                .iter()
+                .position(|(_, from, to)| volume >= *from && volume < *to)
+            {
+                counts[i] += 1;
+            }
+        }
+        ranges
+            .iter()
+            .zip(counts)
+            .map(|((label, _, _), count)| (label.to_string(), count))
+            .collect()
+    }
+
+    /// Find alias of the peer for channel of any type
+    pub fn get_channel_alias(&self, channel_id: &str) -> Option<String> {
+        self.channels_stats
+            .iter()
+            .chain(self.hosted_stats.iter())
+            .chain(self.fiat_stats.iter())
+            .find(|c| c.chan_id == channel_id)
+            .map(|c| c.alias.clone())
+    }
+
+    /// Most recent received payments, newest first
+    pub fn get_received_payments(&self, limit: usize) -> Vec<ReceivedPayment> {
+        let mut payments: Vec<ReceivedPayment> = self
+            .audit
+            .received
+            .iter()
+            .map(|p| ReceivedPayment {
+                payment_hash: p.payment_hash.clone(),
+                amount: p.parts.iter().map(|part| part.amount).sum(),
+                timestamp: p.parts.iter().map(|part| part.timestamp.unix).max().unwrap_or(0),
+                sources: p
+                    .parts
+                    .iter()
+                    .map(|part| {
+                        self.get_channel_alias(&part.from_channel_id)
+                            .unwrap_or_else(|| part.from_channel_id.clone())
+                    })
+                    .unique()
+                    .collect(),
+                description: self
+                    .incoming_payment(&p.payment_hash)
+                    .and_then(|i| i.payment_request.description.clone()),
+                status: self
+                    .incoming_payment(&p.payment_hash)
+                    .map(|i| i.status.clone()),
+            })
+            .collect();
+        payments.sort_by_key(|p| std::cmp::Reverse(p.timestamp));
+        payments.truncate(limit);
+        payments
+    }
+
+    fn incoming_payment(&self, payment_hash: &str) -> Option<&IncomingPayment> {
+        self.incoming_payments.get(payment_hash).and_then(|i| i.as_ref())
+    }
+
+    /// How many of the latest received payments we query invoices for at each poll
+    const INCOMING_INFO_LIMIT: usize = 20;
+
+    /// How many invoices we query at most at each poll
+    const INCOMING_QUERY_LIMIT: usize = 5;
+
+    /// How long we wait before querying the invoice again after a failure or a status that
+    /// may still change, in seconds
+    const INCOMING_RETRY_DELAY: i64 = 600;
+
+    /// Hashes of the latest received payments whose invoices we don't know or whose
+    /// statuses may still change, except ones that we queried recently
+    fn incoming_payments_to_query(&self, now: i64) -> Vec<String> {
+        self.get_received_payments(App::INCOMING_INFO_LIMIT)
+            .into_iter()
+            .map(|p| p.payment_hash)
+            .filter(|hash| match self.incoming_payments.get(hash) {
+                Some(None) => false,
+                Some(Some(payment)) => !payment.status.is_final(),
+                None => true,
+            })
+            .filter(|hash| {
+                self.incoming_queried
+                    .get(hash)
+                    .is_none_or(|queried| now - queried > App::INCOMING_RETRY_DELAY)
+            })
+            .take(App::INCOMING_QUERY_LIMIT)
+            .collect()
+    }
+
+    /// Forget invoices of payments that left the audit window
+    fn prune_incoming_payments(&mut self) {
+        let received: HashSet<&str> =
+            self.audit.received.iter().map(|r| &r.payment_hash[..]).collect();
+        self.incoming_payments.retain(|hash, _| received.contains(&hash[..]));
+        self.incoming_queried.retain(|hash, _| received.contains(&hash[..]));
+    }
+
+    /// Currencies of fiat channels in alphabetical order
+    pub fn fiat_currencies(&self) -> Vec<&str> {
+        self.fiat_stats
+            .iter()
+            .map(|s| s.currency())
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    pub fn get_total_fiat_balance(&self, currency: &str) -> f64 {
+        self.get_fiat_balance_by(currency, |_| true)
+    }
+
+    /// How much of BTC in our channels has its value fixed in fiat by fiat channels. Fiat
+    /// balances in different currencies are not summed, see `get_total_fiat_balance`.
+    pub fn fiat_exposure(&self) -> FiatExposure {
+        // BTC backing fiat balances of clients, independent of `--fiat-valuation`
+        let hedged = saturating_sum(self.fiat_stats.iter().map(|c| c.remote));
+        let unhedged = saturating_sum(
+            self.channels_stats
+                .iter()
+                .chain(self.fiat_stats.iter())
+                .filter(|c| c.chan_state.is_normal())
+                .map(|c| c.local),
+        );
+        let total = hedged.saturating_add(unhedged);
+        let hedge_ratio = if total == 0 {
+            0.0
+        } else {
+            hedged as f64 / total as f64
+        };
+        FiatExposure {
+            hedged,
+            unhedged,
+            hedge_ratio,
+        }
+    }
+
+    /// How many rate changes we keep for each fiat channel
+    const FIAT_RATES_LIMIT: usize = 100;
+
+    /// Save current rates of fiat channels if they changed since the last poll and update
+    /// the rate deltas that the Fiat tab shows
+    pub fn record_fiat_rates(&mut self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::FIAT_RATES_TREE)?;
+        let mut deltas = HashMap::new();
+        for chan in self.fiat_stats.iter() {
+            storage::push_changed(&tree, &chan.chan_id, chan.rate(), App::FIAT_RATES_LIMIT)?;
+            let history: Vec<storage::Snapshot<u64>> = storage::load_history(&tree, &chan.chan_id)?;
+            if let [.., (_, previous), (_, current)] = &history[..] {
+                deltas.insert(chan.chan_id.clone(), *current as i64 - *previous as i64);
+            }
+        }
+        self.fiat_rate_deltas = deltas;
+        Ok(())
+    }
+
+    /// Proposals of hosted and fiat channels that wait for the operator, by channel id
+    pub fn pending_proposals(&self) -> Vec<(&str, Proposal)> {
+        let hosted = self.hc_channels.iter().flat_map(|(id, c)| {
+            Proposal::from_hosted(c).into_iter().map(move |p| (&id[..], p))
+        });
+        let fiat = self.fc_channels.iter().flat_map(|(id, c)| {
+            Proposal::from_fiat(c).into_iter().map(move |p| (&id[..], p))
+        });
+        hosted.chain(fiat).sorted_by_key(|(id, _)| *id).collect()
+    }
+
+    /// Pending proposals of the hosted or fiat channel
+    pub fn channel_proposals(&self, channel_id: &str) -> Vec<Proposal> {
+        match (self.hc_channels.get(channel_id), self.fc_channels.get(channel_id)) {
+            (Some(chan), _) => Proposal::from_hosted(chan),
+            (_, Some(chan)) => Proposal::from_fiat(chan),
+            _ => vec![],
+        }
+    }
+
+    /// Amount of channels with pending proposals shown on the tab
+    pub fn tab_proposals_count(&self, tab: Tab) -> usize {
+        match tab {
+            Tab::Hosted => self
+                .hc_channels
+                .values()
+                .filter(|c| !Proposal::from_hosted(c).is_empty())
+                .count(),
+            Tab::Fiat => self
+                .fc_channels
+                .values()
+                .filter(|c| !Proposal::from_fiat(c).is_empty())
+                .count(),
+            _ => 0,
+        }
+    }
+
+    /// Margin adjustment of the fiat channel that waits for the operator
+    pub fn margin_proposal(&self, channel_id: &str) -> Option<&MarginProposal> {
+        self.fc_channels
+            .get(channel_id)
+            .and_then(|c| c.data.margin_proposal.as_ref())
+    }
+
+    /// Fiat channels with pending margin proposals by channel id
+    pub fn pending_margin_proposals(&self) -> Vec<(&str, &MarginProposal)> {
+        self.fc_channels
+            .iter()
+            .filter_map(|(id, c)| c.data.margin_proposal.as_ref().map(|p| (&id[..], p)))
+            .sorted_by_key(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Change of the fiat channel rate (msat per fiat unit) against the previous different
+    /// rate. The history only records changes, so the delta stays shown while the rate is
+    /// stable rather than dropping to zero on the next poll.
+    pub fn fiat_rate_delta(&self, channel_id: &str) -> Option<i64> {
+        self.fiat_rate_deltas.get(channel_id).copied()
+    }
+
+    /// How many state changes we keep per channel, enough to follow a channel that flaps
+    /// between offline and syncing
+    const STATE_HISTORY_LIMIT: usize = 20;
+
+    /// Remember when channels change their states, zombie and stuck channels are found
+    /// from the histories
+    pub fn record_channel_states(&mut self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::STATE_HISTORY_TREE)?;
+        let mut histories = HashMap::new();
+        for chan in self.channels.iter() {
+            storage::push_changed(&tree, &chan.channel_id, chan.state, App::STATE_HISTORY_LIMIT)?;
+            histories.insert(chan.channel_id.clone(), storage::load_history(&tree, &chan.channel_id)?);
+        }
+        // The start of an offline run is kept apart, the history may lose it when the
+        // channel flaps between offline and syncing
+        let since_tree = self.db.open_tree(storage::SLEEPING_SINCE_TREE)?;
+        let mut sleeping_since = HashMap::new();
+        for (chan_id, history) in histories.iter() {
+            let known = storage::load_value(&since_tree, chan_id)?;
+            match history::run_start(history, &OFFLINE_STATES, known) {
+                Some(since) => {
+                    if known != Some(since) {
+                        storage::store_value(&since_tree, chan_id, &since)?;
+                    }
+                    sleeping_since.insert(chan_id.clone(), since);
+                }
+                None if known.is_some() => {
+                    since_tree.remove(chan_id)?;
+                }
+                None => (),
+            }
+        }
+        // Forget channels that the node doesn't report anymore
+        for tree in [&tree, &since_tree] {
+            for key in tree.iter().keys() {
+                let key = key?;
+                if !histories.contains_key(&*String::from_utf8_lossy(&key)) {
+                    tree.remove(key)?;
+                }
+            }
+        }
+        self.state_history = histories;
+        self.sleeping_since = sleeping_since;
+        Ok(())
+    }
+
+    /// Seed state histories from the states that earlier versions kept, so stuck channels
+    /// don't start counting again after an upgrade
+    fn migrate_state_since(&self) -> storage::Result<()> {
+        let old_exists = self
+            .db
+            .tree_names()
+            .iter()
+            .any(|name| &name[..] == storage::STATE_SINCE_TREE.as_bytes());
+        if !old_exists {
+            return Ok(());
+        }
+        let old = self.db.open_tree(storage::STATE_SINCE_TREE)?;
+        let tree = self.db.open_tree(storage::STATE_HISTORY_TREE)?;
+        for (chan_id, (state, since)) in storage::load_values::<(ChannelState, i64)>(&old)? {
+            if !tree.contains_key(&chan_id)? {
+                storage::store_value(&tree, &chan_id, &vec![(since, state)])?;
+            }
+        }
+        self.db.drop_tree(storage::STATE_SINCE_TREE)?;
+        Ok(())
+    }
+
+    fn load_pinned(&self) -> storage::Result<HashSet<String>> {
+        let tree = self.db.open_tree(storage::PINNED_TREE)?;
+        let pinned: Vec<(String, i64)> = storage::load_values(&tree)?;
+        Ok(pinned.into_iter().map(|(id, _)| id).collect())
+    }
+
+    /// Pin the channel to the top of lists or unpin it if it is already pinned
+    pub fn toggle_pin(&mut self, channel_id: &str) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::PINNED_TREE)?;
+        if self.pinned.remove(channel_id) {
+            tree.remove(channel_id)?;
+        } else {
+            let now = chrono::offset::Utc::now().timestamp();
+            storage::store_value(&tree, channel_id, &now)?;
+            self.pinned.insert(channel_id.to_owned());
+        }
+        Ok(())
+    }
+
+    /// Msat amount for compact channel widgets, abbreviated unless `--full-amounts` is set
+    pub fn short_amount(&self, msat: u64) -> String {
+        if self.abbreviate_amounts {
+            self.amount_unit.format_short(msat)
+        } else {
+            self.amount_unit.format(msat)
+        }
+    }
+
+    /// Count for compact channel widgets, abbreviated unless `--full-amounts` is set
+    pub fn short_count(&self, count: u64) -> String {
+        if self.abbreviate_amounts {
+            abbreviate(count)
+        } else {
+            count.to_formatted_string(&Locale::en)
+        }
+    }
+
+    /// Alias to show for the node, a stable label derived from the node id in the anonymized
+    /// mode
+    pub fn shown_alias(&self, alias: &str, node_id: &str) -> String {
+        if self.anonymize {
+            format!("node-{:06x}", stable_hash(node_id) & 0xffffff)
+        } else {
+            alias.to_owned()
+        }
+    }
+
+    /// Node, channel or short channel id to show
+    pub fn shown_id(&self, id: &str) -> String {
+        if self.anonymize {
+            format!("{:016x}", stable_hash(id))
+        } else {
+            id.to_owned()
+        }
+    }
+
+    /// Network address to show
+    pub fn shown_address(&self, address: &str) -> String {
+        if self.anonymize {
+            format!("addr-{:06x}", stable_hash(address) & 0xffffff)
+        } else {
+            address.to_owned()
+        }
+    }
+
+    /// Text to show with ids, aliases and addresses of the known nodes and channels replaced
+    /// like `shown_alias`, `shown_id` and `shown_address` do. Errors and command results are
+    /// kept raw and pass through here when drawn, so toggling `A` applies to them too.
+    pub fn shown_text(&self, text: &str) -> String {
+        if !self.anonymize {
+            return text.to_owned();
+        }
+        let stats = || {
+            self.channels_stats
+                .iter()
+                .chain(self.hosted_stats.iter())
+                .chain(self.fiat_stats.iter())
+        };
+        let node_ids = stats()
+            .map(|c| &c.node_id)
+            .chain(self.peers.iter().map(|p| &p.node_id))
+            .chain(self.known_nodes.keys())
+            .chain(self.alias_map.keys())
+            .unique();
+        let mut labels: Vec<(String, String)> = vec![];
+        for node_id in node_ids {
+            let alias = self.get_peer_alias(node_id);
+            labels.push((self.shown_alias(&alias, node_id), alias));
+            labels.push((self.shown_id(node_id), node_id.clone()));
+        }
+        let own = &self.node_info;
+        labels.push((self.shown_alias(&own.alias, &own.node_id), own.alias.clone()));
+        labels.push((self.shown_id(&own.node_id), own.node_id.clone()));
+        let addresses = self
+            .peers
+            .iter()
+            .filter_map(|p| p.address.as_ref())
+            .chain(self.known_nodes.values().flat_map(|n| n.addresses.iter()));
+        for address in addresses {
+            labels.push((self.shown_address(address), address.clone()));
+        }
+        for chan in self.channels.iter() {
+            labels.push((self.shown_id(&chan.channel_id), chan.channel_id.clone()));
+            if let Some(scid) = chan.data.as_ref().and_then(|d| d.short_channel_id.as_ref()) {
+                labels.push((self.shown_id(scid), scid.clone()));
+            }
+        }
+        for chan in stats() {
+            labels.push((self.shown_id(&chan.chan_id), chan.chan_id.clone()));
+        }
+        // Longer values go first, so an alias that is a part of an id doesn't break it
+        labels.sort_by_key(|(_, raw)| std::cmp::Reverse(raw.len()));
+        let mut shown = text.to_owned();
+        for (label, raw) in labels {
+            if !raw.is_empty() && shown.contains(&raw) {
+                shown = shown.replace(&raw, &label);
+            }
+        }
+        shown
+    }
+
+    /// Placeholder for hidden balances
+    const HIDDEN_BALANCE: &'static str = "***";
+
+    /// Balance to show, `None` when it is hidden
+    fn shown_balance(&self, msat: u64) -> Option<u64> {
+        match (self.anonymize, self.anonymize_hide_balances) {
+            (false, _) => Some(msat),
+            (true, false) => Some(round_significant(msat)),
+            (true, true) => None,
+        }
+    }
+
+    /// Balance in the selected unit, rounded or hidden in the anonymized mode
+    pub fn format_balance(&self, msat: u64) -> String {
+        match self.shown_balance(msat) {
+            Some(msat) => self.amount_unit.format(msat),
+            None => Self::HIDDEN_BALANCE.to_owned(),
+        }
+    }
+
+    /// Balance for compact channel widgets, see `short_amount`
+    pub fn short_balance(&self, msat: u64) -> String {
+        match self.shown_balance(msat) {
+            Some(msat) => self.short_amount(msat),
+            None => Self::HIDDEN_BALANCE.to_owned(),
+        }
+    }
+
+    /// Balance in sats for gauge labels, see `short_count`
+    pub fn short_balance_sats(&self, msat: u64) -> String {
+        match self.shown_balance(msat) {
+            Some(msat) => self.short_count(msat / 1000),
+            None => Self::HIDDEN_BALANCE.to_owned(),
+        }
+    }
+
+    /// Fiat amount of fiat channels with `--fiat-decimals` places
+    pub fn format_fiat(&self, value: f64, currency: &str) -> String {
+        format!("{:.*} {}", self.fiat_decimals, value, currency_symbol(currency))
+    }
+
+    /// Fiat balance, rounded to whole units or hidden in the anonymized mode
+    pub fn format_fiat_balance(&self, value: f64, currency: &str) -> String {
+        match (self.anonymize, self.anonymize_hide_balances) {
+            (false, _) => self.format_fiat(value, currency),
+            (true, false) => format!("{:.0} {}", value, currency_symbol(currency)),
+            (true, true) => Self::HIDDEN_BALANCE.to_owned(),
+        }
+    }
+
+    pub fn is_pinned(&self, chan: &ChannelStats) -> bool {
+        self.pinned.contains(&chan.chan_id)
+    }
+
+    /// Order that puts pinned channels before the others
+    pub fn pinned_first(&self, a: &ChannelStats, b: &ChannelStats) -> Ordering {
+        self.is_pinned(b).cmp(&self.is_pinned(a))
+    }
+
+    fn toggle_pin_selected(&mut self) {
+        let channel_id = match self.get_selected_channel() {
+            Some(chan) => chan.chan_id.clone(),
+            None => return,
+        };
+        // The channel stays selected after it moves in the list, it is tracked by id
+        if let Err(e) = self.toggle_pin(&channel_id) {
+            self.push_error(format!("Failed to pin channel {}: {}", channel_id, e));
+        }
+    }
+
+    /// How long the channel is offline or syncing in seconds
+    pub fn offline_duration(&self, channel_id: &str) -> Option<i64> {
+        self.sleeping_since
+            .get(channel_id)
+            .map(|since| chrono::offset::Utc::now().timestamp() - since)
+    }
+
+    /// Channel is offline for so long that it is effectively dead
+    pub fn is_zombie(&self, chan: &ChannelStats) -> bool {
+        self.offline_duration(&chan.chan_id)
+            .is_some_and(|duration| duration > self.zombie_days * 24 * 3600)
+    }
+
+    /// How long the channel is syncing or waits for the funding confirmation in seconds
+    pub fn stuck_duration(&self, chan: &ChannelStats) -> Option<i64> {
+        match chan.chan_state {
+            ChannelState::Syncing | ChannelState::WaitForFundingConfirmed => {
+                history::duration_in(
+                    self.state_history.get(&chan.chan_id)?,
+                    &[chan.chan_state],
+                    None,
+                    chrono::offset::Utc::now().timestamp(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Channel doesn't leave syncing or the funding confirmation, operator should step in
+    pub fn is_stuck(&self, chan: &ChannelStats) -> bool {
+        self.stuck_duration(chan)
+            .is_some_and(|duration| duration > self.stuck_hours * 3600)
+    }
+
+    /// Channels that are stuck in syncing or the funding confirmation, the longest first
+    pub fn stuck_channels(&self) -> Vec<&ChannelStats> {
+        self.channels_stats
+            .iter()
+            .filter(|c| self.is_stuck(c))
+            .sorted_by_key(|c| std::cmp::Reverse(self.stuck_duration(c)))
+            .collect()
+    }
+
+    /// Recalculate sparklines and totals of relays from the audit
+    fn update_relays_stats(&mut self) {
+        trace!("Calculating relays lines");
+        self.update_relays_lines();
+        trace!("Calculating hourly relays");
+        self.relays_hourly_counts = self.get_relays_hourly_buckets();
+        self.relays_hourly_volumes = self.get_relays_hourly_volumes();
+        self.daily_fees = self.daily_fee_history(App::DAILY_FEES_DAYS);
+
+        trace!("Calculating relays month");
+        self.relayed_month = self.get_relayed(App::MONTH_PERIOD);
+        trace!("Calculating relays day");
+        self.relayed_day = self.get_relayed(App::DAY_PERIOD);
+        trace!("Calculating relays count month");
+        self.relayed_count_month = self.get_relayed_count(App::MONTH_PERIOD);
+        trace!("Calculating relays count day");
+        self.relayed_count_day = self.get_relayed_count(App::DAY_PERIOD);
+
+        trace!("Calculating fees");
+        self.fee_month = self.get_fee(App::MONTH_PERIOD);
+        self.fee_day = self.get_fee(App::DAY_PERIOD);
+        self.fees_paid_month = self.fees_paid(App::MONTH_PERIOD);
+        self.update_period_stats();
+
+        trace!("Calculating hosted relays");
+        self.hosted_totals = self.relay_totals_by(|s| self.is_hosted_relay(s));
+    }
+
+    /// Save the audit to show it right after the next start
+    pub fn persist_audit(&self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::AUDIT_TREE)?;
+        storage::store_value(&tree, storage::AUDIT_KEY, &self.audit)
+    }
+
+    fn load_relays_lifetime(&self) -> storage::Result<RelayLifetime> {
+        let tree = self.db.open_tree(storage::RELAYS_LIFETIME_TREE)?;
+        Ok(storage::load_value(&tree, storage::RELAYS_LIFETIME_KEY)?.unwrap_or_default())
+    }
+
+    /// Add relays of the audit that were never counted before to the all-time totals, each
+    /// payment once like the other relay stats. Audit windows of consecutive polls and runs
+    /// overlap, so payment hashes of counted relays are kept until they are older than the
+    /// longest audit window.
+    pub fn accumulate_relays(&mut self) -> storage::Result<()> {
+        let seen = self.db.open_tree(storage::SEEN_RELAYS_TREE)?;
+        let mut lifetime = self.relays_lifetime;
+        let mut counted = 0;
+        let relays = self.audit.relayed.iter().filter(|r| self.is_sane_relay(r));
+        for relay in relays {
+            // Most relays were counted by previous polls, so only new ones are written.
+            // Earlier versions keyed the records by `event_key` that starts with the payment
+            // hash, the prefix scan finds those too.
+            let key = &relay.payment_hash;
+            if seen.scan_prefix(key).next().transpose()?.is_some() {
+                continue;
+            }
+            seen.insert(key, serde_json::to_vec(&relay.timestamp.unix)?)?;
+            lifetime.count += 1;
+            lifetime.volume = lifetime.volume.saturating_add(relay.amount_in);
+            counted += 1;
+        }
+        if counted > 0 {
+            let tree = self.db.open_tree(storage::RELAYS_LIFETIME_TREE)?;
+            storage::store_value(&tree, storage::RELAYS_LIFETIME_KEY, &lifetime)?;
+            self.relays_lifetime = lifetime;
+        }
+        let now = chrono::offset::Utc::now().timestamp();
+        if now - self.relays_pruned > App::DAY_PERIOD {
+            App::prune_seen_relays(&seen, now - App::YEAR_PERIOD)?;
+            self.relays_pruned = now;
+        }
+        Ok(())
+    }
+
+    /// Forget counted relays before `from`, no audit window reaches them anymore
+    fn prune_seen_relays(seen: &sled::Tree, from: i64) -> storage::Result<()> {
+        for entry in seen.iter() {
+            let (key, value) = entry?;
+            let timestamp: u64 = serde_json::from_slice(&value)?;
+            if (timestamp as i64) < from {
+                seen.remove(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the audit saved by the previous run, if any
+    pub fn load_audit(&mut self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::AUDIT_TREE)?;
+        if let Some(audit) = storage::load_value(&tree, storage::AUDIT_KEY)? {
+            self.audit = audit;
+        }
+        Ok(())
+    }
+
+    /// Add the fresh audit, full or only the recent part of it, to the known one. Fresh
+    /// records replace the known ones, known records missing in the response stay while
+    /// they are in the audit window of `period`. Returns whether the audit changed.
+    fn merge_audit(&mut self, fresh: AuditInfo, period: i64) -> bool {
+        let from = chrono::offset::Utc::now().timestamp() as u64 - period as u64;
+        let mut merged = AuditInfo {
+            sent: merge_records(&self.audit.sent, fresh.sent, |s| s.id.clone(), |s| {
+                s.parts.iter().any(|p| p.timestamp.unix > from)
+            }),
+            received: merge_records(
+                &self.audit.received,
+                fresh.received,
+                |r| r.payment_hash.clone(),
+                |r| r.parts.iter().any(|p| p.timestamp.unix > from),
+            ),
+            relayed: merge_records(&self.audit.relayed, fresh.relayed, |r| r.event_key(), |r| {
+                r.timestamp.unix > from
+            }),
+        };
+        merged.relayed.sort_by_key(|r| r.timestamp.unix);
+        let changed = merged != self.audit;
+        self.audit = merged;
+        changed
+    }
+
+    /// How often we fetch the whole audit period even if we have it, in case the node
+    /// recorded something in the past, e.g. after restoring its database
+    const AUDIT_FULL_REFRESH: i64 = 24 * 3600;
+
+    /// Overlap of consecutive audit fetches, so relays that eclair records with a delay
+    /// are not missed
+    const AUDIT_OVERLAP: i64 = 3600;
+
+    /// Period of audit to request in the next poll. The whole audit period, a year with
+    /// the year figures, is fetched at startup, when the period grows and once a day;
+    /// other polls only fetch what happened since the previous one.
+    pub fn audit_request_period(&self, now: i64) -> i64 {
+        let full = self.audit_period();
+        if self.audit_fetched_period < full || now - self.audit_full_at > App::AUDIT_FULL_REFRESH {
+            full
+        } else {
+            (now - self.audit_fetched_at + App::AUDIT_OVERLAP).min(full)
+        }
+    }
+
+    /// How many volume changes we keep for each channel
+    const CHANNEL_VOLUMES_LIMIT: usize = 30;
+
+    /// Save current relayed volumes of channels if they changed since the last poll and
+    /// keep the histories for the sparklines of the Channels tab
+    pub fn record_channel_volumes(&mut self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::CHANNEL_VOLUMES_TREE)?;
+        let mut volumes = HashMap::new();
+        for chan in self.channels_stats.iter() {
+            storage::push_changed(&tree, &chan.chan_id, chan.relays_volume, App::CHANNEL_VOLUMES_LIMIT)?;
+            let history = storage::load_history::<u64>(&tree, &chan.chan_id)?;
+            volumes.insert(chan.chan_id.clone(), history.into_iter().map(|(_, volume)| volume).collect());
+        }
+        self.channel_volumes = volumes;
+        Ok(())
+    }
+
+    /// Recorded relayed volumes (msat) of the channel, oldest first
+    pub fn channel_volume_history(&self, channel_id: &str) -> &[u64] {
+        self.channel_volumes.get(channel_id).map_or(&[], |history| &history[..])
+    }
+
+    /// How many days of earned fees we keep and show per channel
+    pub const CHANNEL_FEE_DAYS: i64 = 30;
+
+    /// Save fees that each channel earned in each day covered by the audit, like
+    /// `record_daily_fees` does for the whole node, and keep the histories for the detail
+    /// popup. Fees are earned by the outgoing channel, whose policy charged them.
+    pub fn record_channel_fees(&mut self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::CHANNEL_DAILY_FEES_TREE)?;
+        let mut by_channel: HashMap<&str, BTreeMap<String, u64>> = HashMap::new();
+        for r in self.iterate_relays(App::MONTH_PERIOD) {
+            let t = chrono::NaiveDateTime::from_timestamp(r.timestamp.unix as i64, 0);
+            let fee = by_channel
+                .entry(&r.to_channel_id)
+                .or_default()
+                .entry(t.format(DAY_FORMAT).to_string())
+                .or_insert(0);
+            *fee = fee.saturating_add(r.amount_in.saturating_sub(r.amount_out));
+        }
+        let today = chrono::offset::Utc::today().naive_utc();
+        let days: Vec<String> = (0..App::CHANNEL_FEE_DAYS)
+            .rev()
+            .map(|i| (today - chrono::Duration::days(i)).format(DAY_FORMAT).to_string())
+            .collect();
+        let mut histories = HashMap::new();
+        for chan in self.channels_stats.iter() {
+            let mut stored: BTreeMap<String, u64> =
+                storage::load_value(&tree, &chan.chan_id)?.unwrap_or_default();
+            let fresh = by_channel.remove(&chan.chan_id[..]).unwrap_or_default();
+            // The audit may start in the middle of a day, never lower an earlier value
+            let mut changed = false;
+            for (day, fee) in fresh {
+                if stored.get(&day).is_none_or(|old| fee > *old) {
+                    stored.insert(day, fee);
+                    changed = true;
+                }
+            }
+            let first = &days[0];
+            if stored.keys().next().is_some_and(|day| day < first) {
+                stored.retain(|day, _| day >= first);
+                changed = true;
+            }
+            if changed {
+                storage::store_value(&tree, &chan.chan_id, &stored)?;
+            }
+            let history = days.iter().map(|day| stored.get(day).copied().unwrap_or(0)).collect();
+            histories.insert(chan.chan_id.clone(), history);
+        }
+        self.channel_fees = histories;
+        Ok(())
+    }
+
+    /// Fees (msat) that the channel earned in each of the last `CHANNEL_FEE_DAYS` days,
+    /// oldest first. Days when we didn't run have zero fees.
+    pub fn channel_fee_history(&self, channel_id: &str) -> &[u64] {
+        self.channel_fees.get(channel_id).map_or(&[], |history| &history[..])
+    }
+
+    /// How many days the daily fees chart shows
+    pub const DAILY_FEES_DAYS: i64 = 30;
+
+    /// Save fees earned in each day covered by the audit. The audit may start in the middle
+    /// of a day, so we never lower the value recorded by an earlier poll.
+    pub fn record_daily_fees(&self) -> storage::Result<()> {
+        let tree = self.db.open_tree(storage::DAILY_FEES_TREE)?;
+        let by_day = self
+            .iterate_relays(App::MONTH_PERIOD)
+            .map(|r| {
+                let t = chrono::NaiveDateTime::from_timestamp(r.timestamp.unix as i64, 0);
+                (t.format(DAY_FORMAT).to_string(), r.amount_in.saturating_sub(r.amount_out))
+            })
+            .into_group_map();
+        for (day, fees) in by_day {
+            let fee = saturating_sum(fees.into_iter());
+            let stored: u64 = storage::load_value(&tree, &day)?.unwrap_or(0);
+            if fee > stored {
+                storage::store_value(&tree, &day, &fee)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Recorded fees in sats for each of the last `days` days, oldest first. Days when we
+    /// didn't run have zero fees.
+    pub fn daily_fee_history(&self, days: i64) -> Vec<(String, u64)> {
+        let tree = match self.db.open_tree(storage::DAILY_FEES_TREE) {
+            Ok(tree) => tree,
+            Err(e) => {
+                warn!("Failed to open daily fees: {}", e);
+                return vec![];
+            }
+        };
+        let today = chrono::offset::Utc::today().naive_utc();
+        (0..days)
+            .rev()
+            .map(|i| {
+                let day = today - chrono::Duration::days(i);
+                let fee: u64 = storage::load_value(&tree, &day.format(DAY_FORMAT).to_string())
+                    .ok()
+                    .flatten()
+                    .unwrap_or(0);
+                (day.format("%d").to_string(), fee / 1000)
+            })
+            .collect()
+    }
+
+    /// Sum of fiat balances in the currency of channels whose state passes the filter
+    pub fn get_fiat_balance_by<F: FnOnce(ChannelState) -> bool + Copy>(
+        &self,
+        currency: &str,
+        f: F,
+    ) -> f64 {
+        self.fiat_stats
+            .iter()
+            .filter_map(|c| {
+                if c.currency() == currency && f(c.chan_state) {
+                    Some(c.fiat_balance())
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    /// Iterate sane relays in the given interval, each payment once
+    fn iterate_relays(&self, interval: i64) -> impl Iterator<Item = &RelayedInfo> {
+        let now = chrono::offset::Utc::now().timestamp();
+        self.audit
+            .relays_since((now - interval) as u64, move |r| self.is_sane_relay(r))
+    }
+
+    /// Count, volume and fees of relays in the given interval
+    fn relay_sums(&self, interval: i64) -> RelaySums {
+        let now = chrono::offset::Utc::now().timestamp();
+        self.audit
+            .relay_sums((now - interval) as u64, |r| self.is_sane_relay(r))
+    }
+
+    /// Most recent relays through the channel in either direction, newest first
+    pub fn channel_relays(&self, channel_id: &str, limit: usize) -> Vec<&RelayedInfo> {
+        self.audit
+            .relayed
+            .iter()
+            .filter(|r| r.from_channel_id == channel_id || r.to_channel_id == channel_id)
+            .sorted_by(|a, b| b.timestamp.unix.cmp(&a.timestamp.unix))
+            .take(limit)
+            .collect()
+    }
+
+    /// Relayed volume (msat) over the last hour and over the hour before it
+    pub fn recent_throughput(&self) -> (u64, u64) {
+        const HOUR: u64 = 3600;
+        let now = chrono::offset::Utc::now().timestamp() as u64;
+        let volume_between = |from: u64, to: u64| {
+            saturating_sum(
+                self.iterate_relays(2 * HOUR as i64)
+                    .filter(|r| r.timestamp.unix > from && r.timestamp.unix <= to)
+                    .map(|r| r.amount_in),
+            )
+        };
+        (
+            volume_between(now - HOUR, now),
+            volume_between(now - 2 * HOUR, now - HOUR),
+        )
+    }
+
+    /// Check that the relay record is not malformed, e.g. by amounts in different units. Sane
+    /// relay never sends more than it receives and its fee is within `max_fee_ratio` of amount.
+    pub fn is_sane_relay(&self, relay: &RelayedInfo) -> bool {
+        relay.amount_out <= relay.amount_in
+            && (relay.amount_in - relay.amount_out) as f64
+                <= self.max_fee_ratio * relay.amount_in as f64
+    }
+
+    /// Remember the error of the plugin endpoint or forget it if the plugin works again
+    fn set_plugin_error(&mut self, plugin: NodePlugin, error: Option<String>) {
+        match error {
+            Some(e) => {
+                if !self.plugin_errors.contains_key(&plugin) {
+                    warn!("The {} plugin fails: {}", plugin, e);
+                }
+                self.plugin_errors.insert(plugin, e);
+            }
+            None => {
+                if self.plugin_errors.remove(&plugin).is_some() {
+                    info!("The {} plugin works again", plugin);
+                }
+            }
+        }
+    }
+
+    /// Report malformed relays that we exclude from the stats
+    fn warn_insane_relays(&self) {
+        let insane: Vec<&RelayedInfo> = self
+            .audit
+            .relayed
+            .iter()
+            .filter(|r| !self.is_sane_relay(r))
+            .collect();
+        if !insane.is_empty() {
+            warn!(
+                "Excluding {} relays with fee above {:.1}% of amount from stats",
+                insane.len(),
+                self.max_fee_ratio * 100.0
+            );
+            for r in insane {
+                debug!(
+                    "Malformed relay {}: in {} msat, out {} msat",
+                    r.payment_hash, r.amount_in, r.amount_out
+                );
+            }
+        }
+    }
+
+    /// Day and month totals of relays that pass the filter
+    fn relay_totals_by<F: Fn(&RelayedInfo) -> bool>(&self, filter: F) -> RelayTotals {
+        const DAY: i64 = 24 * 3600;
+        let sums =
+            |interval: i64| RelaySums::of(self.iterate_relays(interval).filter(|s| filter(s)));
+        let (day, month) = (sums(DAY), sums(App::MONTH_PERIOD));
+        RelayTotals {
+            count_day: day.count,
+            count_month: month.count,
+            relayed_day: day.volume,
+            relayed_month: month.volume,
+            fee_day: day.fee,
+            fee_month: month.fee,
+        }
+    }
+
+    /// Volume (msat) relayed in the given interval
+    pub fn get_relayed(&self, interval: i64) -> u64 {
+        self.relay_sums(interval).volume
+    }
+
+    /// Amount of relays in the given interval
+    pub fn get_relayed_count(&self, interval: i64) -> u64 {
+        self.relay_sums(interval).count
+    }
+
+    /// Fees (msat) earned by relays in the given interval
+    pub fn get_fee(&self, interval: i64) -> u64 {
+        self.relay_sums(interval).fee
+    }
+
+    /// Recalculate relay figures of the selected dashboard period
+    fn update_period_stats(&mut self) {
+        let period = self.stats_period.seconds();
+        self.relayed_count_period = self.get_relayed_count(period);
+        self.relayed_period = self.get_relayed(period);
+        self.fee_period = self.get_fee(period);
+    }
+
+    /// Period of audit to query from the node. We need at least a month for ARP and a
+    /// longer one if the dashboard shows a longer period.
+    pub fn audit_period(&self) -> i64 {
+        self.stats_period.seconds().max(App::MONTH_PERIOD)
+    }
+
+    /// The audit from the node doesn't cover the selected dashboard period yet, e.g. right
+    /// after switching to the year figures
+    pub fn is_period_partial(&self) -> bool {
+        self.audit_fetched_period < self.stats_period.seconds()
+    }
+
+    /// Fees paid for sending our own payments in the given interval. Parts of multi-part
+    /// payments carry their own fees and timestamps.
+    pub fn fees_paid(&self, interval: i64) -> u64 {
+        let now = chrono::offset::Utc::now().timestamp();
+        saturating_sum(
+            self.audit
+                .sent
+                .iter()
+                .flat_map(|s| s.parts.iter())
+                .filter(|p| p.timestamp.unix > (now - interval) as u64)
+                .map(|p| p.fees_paid),
+        )
+    }
+
+    /// Fees earned by relays minus fees paid for own payments over the month
+    pub fn net_fee_month(&self) -> i64 {
+        self.fee_month as i64 - self.fees_paid_month as i64
+    }
+
+    const YEAR_PERIOD: i64 = 365 * 24 * 3600;
+    const MONTH_PERIOD: i64 = 30 * 24 * 3600;
+    const DAY_PERIOD: i64 = 24 * 3600;
+    /// ARP calculated over shorter window is too noisy to trust
+    const PROVISIONAL_PERIOD: i64 = 7 * 24 * 3600;
+
+    /// Length of the observed audit window in seconds, from the oldest relay up to now.
+    /// Doesn't exceed the month that we query from the node.
+    pub fn get_audit_window(&self) -> i64 {
+        let now = chrono::offset::Utc::now().timestamp();
+        self.audit
+            .relayed
+            .iter()
+            .map(|r| r.timestamp.unix as i64)
+            .min()
+            .map_or(0, |t| (now - t).clamp(0, App::MONTH_PERIOD))
+    }
+
+    pub fn get_return_rate(&self) -> f64 {
+        self.return_rate_of(self.fee_month, self.local_volume())
+    }
+
+    /// ARP of hosted channels only
+    pub fn get_hosted_return_rate(&self) -> f64 {
+        self.return_rate_of(self.hosted_totals.fee_month, self.hosted_local_volume())
+    }
+
+    /// Annualized percent that month fees make of the local balance
+    fn return_rate_of(&self, fee_month: u64, local_volume: u64) -> f64 {
+        let window = self.get_audit_window();
+        if window == 0 || local_volume == 0 {
+            return 0.0;
+        }
+        let annualization = App::YEAR_PERIOD as f64 / window as f64;
+        annualization * 100.0 * (fee_month as f64) / (local_volume as f64)
+    }
+
+    pub fn is_return_rate_provisional(&self) -> bool {
+        self.get_audit_window() < App::PROVISIONAL_PERIOD
+    }
+
+    pub fn local_volume(&self) -> u64 {
+        self.active_sats + self.pending_sats + self.sleeping_sats
+    }
+
+    pub fn relayed_percent(&self) -> f64 {
+        100.0 * (self.relayed_month as f64) / (self.local_volume() as f64)
+    }
+
+    /// Local balance of hosted channels
+    pub fn hosted_local_volume(&self) -> u64 {
+        saturating_sum(
+            self.hc_channels
+                .values()
+                .map(|c| c.data.commitments.local_spec.to_local),
+        )
+    }
+
+    /// Percent of the hosted local balance relayed through hosted channels over the month
+    pub fn hosted_relayed_percent(&self) -> f64 {
+        let volume = self.hosted_local_volume();
+        if volume == 0 {
+            return 0.0;
+        }
+        100.0 * (self.hosted_totals.relayed_month as f64) / (volume as f64)
+    }
+
+    /// Active channel is funded, but its balance is too close to the reserve to route
+    pub fn is_below_reserve(&self, chan: &ChannelStats) -> bool {
+        chan.is_normal_channel()
+            && chan.chan_state.is_normal()
+            && self
+                .channels
+                .get(chan.info_id)
+                .and_then(|c| c.spendable())
+                .is_some_and(|spendable| spendable < self.reserve_buffer)
+    }
+
+    pub fn channels_below_reserve(&self) -> Vec<&ChannelStats> {
+        self.channels_stats
+            .iter()
+            .filter(|c| self.is_below_reserve(c))
+            .collect()
+    }
+
+    /// Count of in-flight HTLCs above which we highlight them as suspicious
+    pub const PENDING_HTLCS_WARNING: usize = 20;
+
+    /// Count and total amount (msat) of HTLCs in local commitments of all channels
+    pub fn pending_htlcs(&self) -> (usize, u64) {
         self.channels
             .iter()
-            .filter_map(|c| {
-                if c.state.is_pending() {
-                    c.data.as_ref()
-                } else {
-                    None
-                }
-            })
-            .map(|c| c.commitments.local_commit.spec.to_local)
-            .sum()
+            .filter_map(|c| c.data.as_ref())
+            .flat_map(|d| d.commitments.local_commit.spec.htlcs.iter())
+            .fold((0, 0), |(count, amount), htlc| (count + 1, amount + htlc.add.amount_msat))
     }
 
-    pub fn get_sleeping_sats(&self) -> u64 {
-        self.channels
+    /// Amount of not closed channels with one peer above which the peer is reported
+    const MANY_CHANNELS_WARNING: usize = 5;
+
+    /// Find short channel ids used by several channels and peers with too many channels
+    pub fn get_channel_anomalies(&self) -> Vec<ChannelAnomaly> {
+        let scids = self
+            .channels
             .iter()
-            .filter_map(|c| {
-                if c.state.is_sleeping() {
-                    c.data.as_ref()
-                } else {
-                    None
-                }
-            })
-            .map(|c| c.commitments.local_commit.spec.to_local)
-            .sum()
+            .filter_map(|c| c.data.as_ref()?.short_channel_id.as_deref())
+            .counts();
+        let duplicates = scids
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(scid, count)| ChannelAnomaly::DuplicateScid {
+                scid: scid.to_owned(),
+                count,
+            });
+        let peers = self
+            .channels
+            .iter()
+            .filter(|c| !c.state.is_closed())
+            .map(|c| c.node_id.as_str())
+            .counts();
+        let crowded = peers
+            .into_iter()
+            .filter(|(_, count)| *count > App::MANY_CHANNELS_WARNING)
+            .map(|(node_id, count)| ChannelAnomaly::ManyChannels {
+                node_id: node_id.to_owned(),
+                count,
+            });
+        duplicates
+            .chain(crowded)
+            .sorted_by_key(|a| a.to_string())
+            .collect()
     }
 
-    pub fn get_total_fiat_balance(&self) -> f64 {
-        self.fiat_stats.iter().map(|s| s.fiat_balance()).sum()
+    /// Report anomalies that were not there at the previous poll
+    fn warn_channel_anomalies(&self, previous: &[ChannelAnomaly]) {
+        for anomaly in self.channel_anomalies.iter() {
+            if !previous.contains(anomaly) {
+                warn!("Channels anomaly: {}", anomaly);
+            }
+        }
     }
 
-    pub fn get_fiat_balance_by<F: FnOnce(ChannelState) -> bool + Copy>(&self, f: F) -> f64 {
-        self.fiat_stats
-            .iter()
-            .filter_map(|c| {
-                if f(c.chan_state) {
-                    Some(c.fiat_balance())
-                } else {
-                    None
-                }
-            })
-            .sum()
-    }
+    /// Proportional fee above which an idle channel is flagged as too expensive
+    const HIGH_FEE_PPM: u64 = 5000;
 
-    fn get_relayed(&self, interval: i64) -> u64 {
-        let now = chrono::offset::Utc::now().timestamp();
-        self.audit
-            .relayed
-            .iter()
-            .filter(|s| s.timestamp.unix > (now - interval) as u64)
-            .map(|s| s.amount_in)
-            .sum()
+    /// Our relay fee policy of the channel: base fee in msat and proportional fee in ppm
+    pub fn fee_policy(&self, chan: &ChannelStats) -> Option<(u64, u64)> {
+        self.channels
+            .get(chan.info_id)
+            .and_then(|c| c.data.as_ref())
+            .and_then(|d| d.channel_update.as_ref())
+            .map(|u| (u.fee_base_msat, u.fee_proportional_millionths))
     }
 
-    pub fn get_relayed_month(&self) -> u64 {
-        self.get_relayed(30 * 24 * 3600)
+    /// Check that the fee policy makes sense for the traffic of the channel
+    pub fn fee_policy_flag(&self, chan: &ChannelStats) -> Option<FeePolicyFlag> {
+        let (base, ppm) = self.fee_policy(chan)?;
+        if base == 0 && ppm == 0 && chan.relays_out > 0 {
+            Some(FeePolicyFlag::FreeWhileRouting)
+        } else if ppm > App::HIGH_FEE_PPM && chan.relays_out == 0 {
+            Some(FeePolicyFlag::ExpensiveWhileIdle)
+        } else {
+            None
+        }
     }
 
-    pub fn get_relayed_day(&self) -> u64 {
-        self.get_relayed(24 * 3600)
+    /// How many times a commitment feerate can differ from the median before we flag it
+    const FEERATE_DEVIATION: f64 = 2.0;
+
+    /// Median commitment feerate (sat/kw) across active channels
+    pub fn get_median_feerate(&self) -> Option<u64> {
+        let mut feerates: Vec<u64> = self
+            .iterate_active_chans()
+            .filter_map(|c| c.feerate())
+            .collect();
+        if feerates.is_empty() {
+            return None;
+        }
+        feerates.sort_unstable();
+        let mid = feerates.len() / 2;
+        if feerates.len().is_multiple_of(2) {
+            Some((feerates[mid - 1] + feerates[mid]) / 2)
+        } else {
+            Some(feerates[mid])
+        }
     }
 
-    fn get_relayed_count(&self, interval: i64) -> u64 {
-        let now = chrono::offset::Utc::now().timestamp();
-        self.audit
-            .relayed
-            .iter()
-            .filter(|s| s.timestamp.unix > (now - interval) as u64)
-            .map(|_| 1)
-            .sum()
+    /// Feerate that is far from the median could indicate a stuck fee negotiation
+    pub fn is_feerate_outlier(&self, feerate: u64) -> bool {
+        match self.median_feerate {
+            Some(median) if median > 0 => {
+                let ratio = feerate as f64 / median as f64;
+                !(1.0 / App::FEERATE_DEVIATION..=App::FEERATE_DEVIATION).contains(&ratio)
+            }
+            _ => false,
+        }
     }
 
-    pub fn get_relayed_count_month(&self) -> u64 {
-        self.get_relayed_count(30 * 24 * 3600)
+    const LINE_PERIOD: u64 = 24 * 3600;
+    const LINE_MARGINS: u64 = 2;
+
+    /// Width of the sparklines in cells
+    fn line_width(&self) -> u64 {
+        (self.screen_width as u64).saturating_sub(App::LINE_MARGINS + 1)
     }
 
-    pub fn get_relayed_count_day(&self) -> u64 {
-        self.get_relayed_count(24 * 3600)
+    /// Resolution of the 24h sparklines, one bucket per cell unless the user asked for
+    /// another amount
+    fn line_buckets(&self) -> u64 {
+        match self.sparkline_buckets {
+            Some(buckets) => buckets.max(1) - 1,
+            None => self.line_width(),
+        }
     }
 
-    fn get_fee(&self, interval: i64) -> u64 {
-        let now = chrono::offset::Utc::now().timestamp();
-        self.audit
-            .relayed
-            .iter()
-            .filter(|s| s.timestamp.unix > (now - interval) as u64)
-            .map(|s| s.amount_in - s.amount_out)
-            .sum()
+    /// Widen the line of `line_buckets` to the screen, the sparkline widget draws one
+    /// value per cell
+    fn stretch_line(&self, line: Vec<u64>) -> Vec<u64> {
+        if self.sparkline_buckets.is_none() || line.is_empty() {
+            return line;
+        }
+        let width = self.line_width() as usize + 1;
+        (0..width).map(|i| line[i * line.len() / width]).collect()
     }
 
-    pub fn get_fee_month(&self) -> u64 {
-        self.get_fee(30 * 24 * 3600)
+    /// Recalculate the 24h sparklines of the node, of hosted and of fiat channels
+    fn update_relays_lines(&mut self) {
+        let (amounts, max_amounts) = self.get_relays_amounts_line();
+        self.relays_amounts_line = amounts;
+        self.relays_maximum_count = max_amounts;
+        let (volumes, max_volume) = self.get_relays_volumes_line();
+        self.relays_volumes_line = volumes;
+        self.relays_maximum_volume = max_volume;
+
+        let (amounts, max_amounts) = self.get_hosted_relays_amounts_line();
+        self.hosted_relays_amounts_line = amounts;
+        self.hosted_relays_maximum_count = max_amounts;
+        let (volumes, max_volume) = self.get_hosted_relays_volumes_line();
+        self.hosted_relays_volumes_line = volumes;
+        self.hosted_relays_maximum_volume = max_volume;
+
+        let (amounts, max_amounts) = self.get_fiat_relays_amounts_line();
+        self.fiat_relays_amounts_line = amounts;
+        self.fiat_relays_maximum_count = max_amounts;
+        let (volumes, max_volume) = self.get_fiat_relays_volumes_line();
+        self.fiat_relays_volumes_line = volumes;
+        self.fiat_relays_maximum_volume = max_volume;
     }
 
-    pub fn get_fee_day(&self) -> u64 {
-        self.get_fee(24 * 3600)
+    /// The relay came from or went to a hosted channel
+    fn is_hosted_relay(&self, relay: &RelayedInfo) -> bool {
+        self.hc_channels.contains_key(&relay.from_channel_id)
+            || self.hc_channels.contains_key(&relay.to_channel_id)
     }
 
-    pub fn get_return_rate(&self) -> f64 {
-        12.0 * 100.0 * (self.fee_month as f64) / (self.local_volume() as f64)
+    /// The relay came from or went to a fiat channel
+    fn is_fiat_relay(&self, relay: &RelayedInfo) -> bool {
+        self.fc_channels.contains_key(&relay.from_channel_id)
+            || self.fc_channels.contains_key(&relay.to_channel_id)
     }
 
-    pub fn local_volume(&self) -> u64 {
-        self.active_sats + self.pending_sats + self.sleeping_sats
+    pub fn get_relays_amounts_line(&self) -> (Vec<u64>, u64) {
+        self.relays_amounts_line_by(|_| true)
     }
 
-    pub fn relayed_percent(&self) -> f64 {
-        100.0 * (self.relayed_month as f64) / (self.local_volume() as f64)
+    /// Relays count sparkline of hosted channels only
+    pub fn get_hosted_relays_amounts_line(&self) -> (Vec<u64>, u64) {
+        self.relays_amounts_line_by(|s| self.is_hosted_relay(s))
     }
 
-    const LINE_PERIOD: u64 = 24 * 3600;
-    const LINE_MARGINS: u64 = 2;
+    /// Relays count sparkline of fiat channels only
+    pub fn get_fiat_relays_amounts_line(&self) -> (Vec<u64>, u64) {
+        self.relays_amounts_line_by(|s| self.is_fiat_relay(s))
+    }
 
-    pub fn get_relays_amounts_line(&mut self) -> (Vec<u64>, u64) {
+    fn relays_amounts_line_by<F: Fn(&RelayedInfo) -> bool>(&self, filter: F) -> (Vec<u64>, u64) {
         let now = chrono::offset::Utc::now().timestamp();
         let mut relays: Vec<u64> = self
             .audit
             .relayed
             .iter()
             .filter(|s| s.timestamp.unix > (now - App::LINE_PERIOD as i64) as u64)
+            .filter(|s| filter(s))
             .map(|s| s.timestamp.unix)
             .collect();
         relays.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
 
-        let line_width = self.screen_width as u64 - App::LINE_MARGINS - 1;
+        let line_width = self.line_buckets();
         let mut result = vec![0; line_width as usize + 1];
         let mut max_relay = 0;
         if !relays.is_empty() {
@@ -473,77 +3392,242 @@ impl App {
                 result = vec![];
             }
         }
-        (result, max_relay)
+        (self.stretch_line(result), max_relay)
+    }
+
+    pub fn get_relays_volumes_line(&self) -> (Vec<u64>, u64) {
+        self.relays_volumes_line_by(|_| true)
+    }
+
+    /// Relays volume sparkline of hosted channels only
+    pub fn get_hosted_relays_volumes_line(&self) -> (Vec<u64>, u64) {
+        self.relays_volumes_line_by(|s| self.is_hosted_relay(s))
+    }
+
+    /// Relays volume sparkline of fiat channels only
+    pub fn get_fiat_relays_volumes_line(&self) -> (Vec<u64>, u64) {
+        self.relays_volumes_line_by(|s| self.is_fiat_relay(s))
     }
 
-    pub fn get_relays_volumes_line(&mut self) -> (Vec<u64>, u64) {
+    fn relays_volumes_line_by<F: Fn(&RelayedInfo) -> bool>(&self, filter: F) -> (Vec<u64>, u64) {
         let now = chrono::offset::Utc::now().timestamp();
         let mut relays: Vec<(u64, u64)> = self
             .audit
             .relayed
             .iter()
             .filter(|s| s.timestamp.unix > (now - App::LINE_PERIOD as i64) as u64)
+            .filter(|s| filter(s))
             .map(|s| (s.amount_in, s.timestamp.unix))
             .collect();
         relays.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
 
-        let line_width = self.screen_width as u64 - App::LINE_MARGINS - 1;
-        let mut result = vec![0; line_width as usize + 1];
+        let line_width = self.line_buckets();
+        let mut result = vec![0u64; line_width as usize + 1];
         let mut max_relay = 0;
         if !relays.is_empty() {
             let t0 = now as u64 - App::LINE_PERIOD;
             let t1 = now as u64;
             for (amount, t) in relays.iter() {
                 let i = (((t - t0) as f64) / ((t1 - t0) as f64) * (line_width as f64)) as usize;
-                result[i] += amount;
+                result[i] = result[i].saturating_add(*amount);
             }
 
-            if let Some(max) = result.iter().max() {
-                max_relay = *max;
-                if max_relay > 0 {
-                    result = result
-                        .iter()
-                        .map(|a| (100.0 * (*a as f64) / (max_relay as f64)) as u64)
-                        .collect();
-                } else {
-                    result = vec![];
-                }
-            } else {
-                max_relay = 0;
-                result = vec![];
+            max_relay = result.iter().copied().max().unwrap_or(0);
+            result = scale_line(&result, self.volumes_log_scale);
+        }
+        (self.stretch_line(result), max_relay)
+    }
+
+    /// Relays count splitted in hourly buckets over the stats interval, labeled by hour in
+    /// the display time zone
+    pub fn get_relays_hourly_buckets(&self) -> Vec<(String, u64)> {
+        self.relays_hourly_by(|_| 1)
+    }
+
+    /// Relays volume in sats splitted in hourly buckets over the stats interval
+    pub fn get_relays_hourly_volumes(&self) -> Vec<(String, u64)> {
+        self.relays_hourly_by(|r| r.amount_in / 1000)
+    }
+
+    fn relays_hourly_by<F: Fn(&RelayedInfo) -> u64>(&self, f: F) -> Vec<(String, u64)> {
+        const HOUR: i64 = 3600;
+        let now = chrono::offset::Utc::now().timestamp();
+        let hours = (self.stats_interval / HOUR).max(1);
+        // Align buckets to the hour boundaries to get stable labels
+        let t0 = (now / HOUR - hours + 1) * HOUR;
+        let mut buckets: Vec<(String, u64)> = (0..hours)
+            .map(|i| {
+                (self.timezone.format(t0 + i * HOUR, "%H"), 0)
+            })
+            .collect();
+        for r in self.audit.relayed.iter() {
+            let t = r.timestamp.unix as i64;
+            if t >= t0 && t < now {
+                let bucket = &mut buckets[((t - t0) / HOUR) as usize].1;
+                *bucket = bucket.saturating_add(f(r));
             }
         }
-        (result, max_relay)
+        buckets
     }
 
     pub async fn start_workers(mapp: AppMutex) {
+        // The poller must never stop, otherwise the dashboard silently goes stale
         tokio::spawn({
             let mapp = mapp.clone();
             async move {
-                loop {
-                    let res = query_node_info(mapp.clone()).await;
-                    if let Err(e) = res {
-                        let now = chrono::offset::Utc::now().timestamp();
-                        let estr = format!("App worker failed at {} with: {}", now, e);
+                let poller = mapp.clone();
+                restart_on_exit(
+                    move || poll_node(poller.clone()),
+                    App::WATCHDOG_DELAY,
+                    |reason| {
+                        let estr = format!("Node poller {}, restarting it", reason);
                         error!("{}", estr);
-                        let mut app = mapp.lock().unwrap();
-                        app.errors.push(estr);
+                        lock(&mapp).push_error(estr);
+                    },
+                )
+                .await
+            }
+        });
+
+        tokio::spawn({
+            let mapp = mapp.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(App::DETAIL_REFRESH_PERIOD).await;
+                    if let Err(e) = refresh_detail_channel(mapp.clone()).await {
+                        warn!("Failed to refresh the channel in detail view: {}", e);
                     }
-                    tokio::time::sleep(Duration::from_secs(20)).await;
                 }
             }
         });
+
+        if lock(&mapp).check_reachability {
+            tokio::spawn({
+                let mapp = mapp.clone();
+                async move {
+                    loop {
+                        let addresses = lock(&mapp).addresses_to_probe();
+                        // Wait for the first poll to know the addresses
+                        if addresses.is_empty() {
+                            tokio::time::sleep(App::POLL_PERIOD).await;
+                            continue;
+                        }
+                        probe_addresses(mapp.clone(), addresses).await;
+                        tokio::time::sleep(App::REACHABILITY_PERIOD).await;
+                    }
+                }
+            });
+        }
+
+        let price_source = lock(&mapp).price_source.clone();
+        if let Some(source) = price_source {
+            tokio::spawn(async move {
+                loop {
+                    let price = match source.fetch().await {
+                        Ok(price) => Some(price),
+                        Err(e) => {
+                            warn!("Failed to fetch BTC price: {}", e);
+                            None
+                        }
+                    };
+                    {
+                        let mut app = lock(&mapp);
+                        app.btc_price = price;
+                        // Market and oracle valued balances depend on the price
+                        app.fiat_stats = app.get_fiat_stats();
+                    }
+                    tokio::time::sleep(App::PRICE_PERIOD).await;
+                }
+            });
+        }
+    }
+
+    /// How often we query the node when it responds
+    const POLL_PERIOD: Duration = Duration::from_secs(20);
+    /// Failed polls in a row after which we start backing off
+    const BREAKER_THRESHOLD: u32 = 3;
+    /// Longest delay between polls of a node that doesn't respond
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+    /// Pause before restarting the poller that stopped, so a poller that fails right away
+    /// doesn't spin
+    const WATCHDOG_DELAY: Duration = Duration::from_secs(5);
+
+    /// Seconds left until the next poll after a failed one
+    pub fn retry_in(&self) -> Option<i64> {
+        let now = chrono::offset::Utc::now().timestamp();
+        self.next_retry.map(|at| (at - now).max(0))
     }
 
-    pub fn resize(&mut self, new_width: u16) {
+    /// How often we probe advertised addresses
+    const REACHABILITY_PERIOD: Duration = Duration::from_secs(600);
+
+    /// Advertised addresses of our node and known addresses of the peers
+    pub fn addresses_to_probe(&self) -> Vec<String> {
+        self.node_info
+            .public_addresses
+            .iter()
+            .chain(
+                self.peers
+                    .iter()
+                    .flat_map(|p| self.get_known_addresses(&p.node_id).iter()),
+            )
+            .unique()
+            .cloned()
+            .collect()
+    }
+
+    /// Addresses of the node from gossip
+    pub fn get_known_addresses(&self, node_id: &str) -> &[String] {
+        self.known_nodes
+            .get(node_id)
+            .map_or(&[], |n| &n.addresses[..])
+    }
+
+    /// How often we refresh the channel shown in the detail popup
+    const DETAIL_REFRESH_PERIOD: Duration = Duration::from_secs(5);
+
+    /// Replace the channel with fresh info from the node and recalculate its stats
+    pub fn update_channel(&mut self, info: ChannelInfo) {
+        match self
+            .channels
+            .iter_mut()
+            .find(|c| c.channel_id == info.channel_id)
+        {
+            Some(chan) => *chan = info,
+            None => return,
+        }
+        self.channels_stats = self.get_channels_stats(self.stats_interval);
+    }
+
+    /// How often we update the BTC price
+    const PRICE_PERIOD: Duration = Duration::from_secs(300);
+
+    /// Source of the rates that `--fiat-valuation` selects
+    pub fn rate_source(&self) -> Box<dyn RateSource + '_> {
+        match self.fiat_valuation {
+            FiatValuation::Channel => Box::new(ChannelRate),
+            FiatValuation::Oracle => Box::new(self.oracle_rate()),
+        }
+    }
+
+    fn oracle_rate(&self) -> OracleRate<'_> {
+        OracleRate {
+            btc_price: self.btc_price,
+            currency: &self.price_currency,
+        }
+    }
+
+    /// Value of the amount in the reference currency if we know the price
+    pub fn to_fiat(&self, msat: u64) -> Option<f64> {
+        self.btc_price
+            .map(|price| price * msat as f64 / 100_000_000_000.0)
+    }
+
+    pub fn resize(&mut self, new_width: u16, new_height: u16) {
+        self.screen_height = new_height;
         if self.screen_width != new_width {
             self.screen_width = new_width;
-            let (amounts, max_amounts) = self.get_relays_amounts_line();
-            self.relays_amounts_line = amounts;
-            self.relays_maximum_count = max_amounts;
-            let (volumes, max_volume) = self.get_relays_volumes_line();
-            self.relays_volumes_line = volumes;
-            self.relays_maximum_volume = max_volume;
+            self.update_relays_lines();
         }
     }
 
@@ -563,11 +3647,13 @@ impl App {
             .collect()
     }
 
+    /// Stats of fiat channels grouped by currency
     pub fn get_fiat_stats(&self) -> Vec<ChannelStats> {
         self.fc_channels
             .iter()
             .enumerate()
             .map(|(i, (chanid, c))| self.get_fiat_channel_stats(i, chanid, c))
+            .sorted_by(|a, b| a.currency().cmp(b.currency()))
             .collect()
     }
 
@@ -580,6 +3666,7 @@ impl App {
             .filter(|s| {
                 (s.from_channel_id == chan.channel_id || s.to_channel_id == chan.channel_id)
                     && s.timestamp.unix > (now - interval) as u64
+                    && self.is_sane_relay(s)
             })
             .collect();
 
@@ -587,11 +3674,7 @@ impl App {
             chan_state: chan.state,
             node_id: chan.node_id.clone(),
             chan_id: chan.channel_id.clone(),
-            alias: self
-                .known_nodes
-                .get(&chan.node_id)
-                .map(|n| n.alias.clone())
-                .unwrap_or_else(|| chan.node_id.clone()),
+            alias: self.get_peer_alias(&chan.node_id),
             local: chan
                 .data
                 .as_ref()
@@ -601,8 +3684,14 @@ impl App {
                 .as_ref()
                 .map_or(0, |c| c.commitments.local_commit.spec.to_remote),
             relays_amount: relays.iter().map(|_| 1).sum(),
-            relays_volume: relays.iter().map(|r| r.amount_in).sum(),
-            relays_fees: relays.iter().map(|r| r.amount_in - r.amount_out).sum(),
+            relays_out: relays
+                .iter()
+                .filter(|r| r.to_channel_id == chan.channel_id)
+                .count() as u64,
+            relays_volume: saturating_sum(relays.iter().map(|r| r.amount_in)),
+            relays_fees: saturating_sum(
+                relays.iter().map(|r| r.amount_in.saturating_sub(r.amount_out)),
+            ),
             info_id: i,
             public: chan.data.as_ref().map_or(false, |c| {
                 c.commitments
@@ -610,12 +3699,51 @@ impl App {
                     .announce_channel
                     .unwrap_or(false)
             }),
-            channel_ext: if chan.data.is_none() {
-                ChannelExt::Hosted
-            } else {
-                ChannelExt::Normal
-            },
+            feerate: chan.feerate(),
+            channel_ext: self.get_channel_ext(chan),
+        }
+    }
+
+    /// Eclair lists channels of the hosted plugins without data. Data of a normal channel can
+    /// be missing too in a weird response, we don't want to show it as hosted then.
+    fn get_channel_ext(&self, chan: &ChannelInfo) -> ChannelExt {
+        match self.channel_kind(chan) {
+            ChannelKind::Normal | ChannelKind::Dataless => ChannelExt::Normal,
+            ChannelKind::Hosted => ChannelExt::Hosted,
+            ChannelKind::Unclassified => ChannelExt::Unclassified,
+        }
+    }
+
+    fn channel_kind(&self, chan: &ChannelInfo) -> ChannelKind {
+        let id = &chan.channel_id;
+        let hosted = self.hc_channels.contains_key(id) || self.fc_channels.contains_key(id);
+        chan.kind(hosted, self.hosted_plugins_known())
+    }
+
+    /// Whether the lists of hosted channels are complete: every hosted plugin is either
+    /// missing on the node or answered at the last poll
+    fn hosted_plugins_known(&self) -> bool {
+        [NodePlugin::HostedChannels, NodePlugin::FiatChannels]
+            .iter()
+            .all(|p| !self.unknown_plugins.contains(p) && !self.plugin_errors.contains_key(p))
+    }
+
+    /// Report channels that come without data and aren't hosted, once per channel
+    fn warn_dataless_channels(&mut self) {
+        let mut dataless = HashSet::new();
+        for chan in self.channels.iter() {
+            if self.channel_kind(chan) != ChannelKind::Dataless {
+                continue;
+            }
+            if !self.warned_dataless.contains(&chan.channel_id) {
+                warn!(
+                    "Channel {} in state {:?} has no data and isn't a hosted channel",
+                    chan.channel_id, chan.state
+                );
+            }
+            dataless.insert(chan.channel_id.clone());
         }
+        self.warned_dataless = dataless;
     }
 
     pub fn get_hosted_channel_stats(
@@ -633,6 +3761,7 @@ impl App {
             .filter(|s| {
                 (s.from_channel_id == channel_id || s.to_channel_id == channel_id)
                     && s.timestamp.unix > (now - interval) as u64
+                    && self.is_sane_relay(s)
             })
             .collect();
         let node_id = &chan.data.commitments.remote_node_id;
@@ -640,18 +3769,18 @@ impl App {
             chan_state: chan.state,
             node_id: node_id.to_owned(),
             chan_id: channel_id.to_owned(),
-            alias: self
-                .known_nodes
-                .get(&chan.data.commitments.remote_node_id)
-                .map(|n| n.alias.clone())
-                .unwrap_or_else(|| node_id.clone()),
+            alias: self.get_peer_alias(node_id),
             local: chan.data.commitments.local_spec.to_local,
             remote: chan.data.commitments.local_spec.to_remote,
             relays_amount: relays.iter().map(|_| 1).sum(),
-            relays_volume: relays.iter().map(|r| r.amount_in).sum(),
-            relays_fees: relays.iter().map(|r| r.amount_in - r.amount_out).sum(),
+            relays_out: relays.iter().filter(|r| r.to_channel_id == channel_id).count() as u64,
+            relays_volume: saturating_sum(relays.iter().map(|r| r.amount_in)),
+            relays_fees: saturating_sum(
+                relays.iter().map(|r| r.amount_in.saturating_sub(r.amount_out)),
+            ),
             public: false,
             info_id: i,
+            feerate: None,
             channel_ext: ChannelExt::Hosted,
         }
     }
@@ -671,6 +3800,7 @@ impl App {
             .filter(|s| {
                 (s.from_channel_id == channel_id || s.to_channel_id == channel_id)
                     && s.timestamp.unix > (now - interval) as u64
+                    && self.is_sane_relay(s)
             })
             .collect();
         let node_id = &chan.data.commitments.remote_node_id;
@@ -680,15 +3810,19 @@ impl App {
             .last_cross_signed_state
             .remote_balance_msat;
         let rate = chan.data.commitments.last_cross_signed_state.rate;
+        let currency = chan
+            .data
+            .currency
+            .clone()
+            .unwrap_or_else(|| FiatChannelData::DEFAULT_CURRENCY.to_owned());
+        let intrinsic_balance = valuation::intrinsic_balance(chan);
+        let market_balance = valuation::client_balance(&self.oracle_rate(), chan, &currency);
+        let fiat_balance = valuation::valued_balance(self.rate_source().as_ref(), chan, &currency);
         ChannelStats {
             chan_state: chan.state,
             node_id: node_id.to_owned(),
             chan_id: channel_id.to_owned(),
-            alias: self
-                .known_nodes
-                .get(&chan.data.commitments.remote_node_id)
-                .map(|n| n.alias.clone())
-                .unwrap_or_else(|| node_id.clone()),
+            alias: self.get_peer_alias(node_id),
             local: chan
                 .data
                 .commitments
@@ -696,99 +3830,423 @@ impl App {
                 .local_balance_msat,
             remote: remote_balance,
             relays_amount: relays.iter().map(|_| 1).sum(),
-            relays_volume: relays.iter().map(|r| r.amount_in).sum(),
-            relays_fees: relays.iter().map(|r| r.amount_in - r.amount_out).sum(),
+            relays_out: relays.iter().filter(|r| r.to_channel_id == channel_id).count() as u64,
+            relays_volume: saturating_sum(relays.iter().map(|r| r.amount_in)),
+            relays_fees: saturating_sum(
+                relays.iter().map(|r| r.amount_in.saturating_sub(r.amount_out)),
+            ),
             info_id: i,
             public: false,
+            feerate: None,
             channel_ext: ChannelExt::HostedFiat(FiatChannelData {
                 rate,
-                fiat_balance: remote_balance as f64 / rate as f64,
+                fiat_balance,
+                intrinsic_balance,
+                market_balance,
+                currency,
             }),
         }
     }
 }
 
+/// Warning to show if the node version is not in the tested range
+pub fn check_version(info: &NodeInfo) -> Option<String> {
+    let (min, max) = SUPPORTED_VERSION_RANGE;
+    match info.parsed_version() {
+        Some(version) if version.is_supported() => None,
+        Some(version) => Some(format!(
+            "Untested eclair {}, supported are {} up to {}",
+            version, min, max
+        )),
+        None => Some(format!("Unknown eclair version {}", info.version)),
+    }
+}
+
+/// Query only the channel that is open in the detail popup, so it stays live between
+/// full polls
+async fn refresh_detail_channel(mapp: AppMutex) -> Result<(), super::api::Error> {
+    let (client, channel_id) = {
+        let app = lock(&mapp);
+        if app.current_tab() != Tab::Channels || !app.detail_open {
+            return Ok(());
+        }
+        match app.get_selected_channel() {
+            Some(chan) => (app.client.clone(), chan.chan_id.clone()),
+            None => return Ok(()),
+        }
+    };
+    trace!("Refreshing channel {} in detail view", channel_id);
+    let info = client.get_channel(&channel_id).await?;
+    lock(&mapp).update_channel(info);
+    Ok(())
+}
+
+/// Try connecting to the addresses concurrently and remember which responded
+async fn probe_addresses(mapp: AppMutex, addresses: Vec<String>) {
+    let probes: Vec<_> = addresses
+        .into_iter()
+        .map(|address| {
+            tokio::spawn(async move {
+                let reachable = reachability::probe(&address).await;
+                (address, reachable)
+            })
+        })
+        .collect();
+    for probe in probes {
+        match probe.await {
+            Ok((address, Some(reachable))) => {
+                debug!("Address {} reachable: {}", address, reachable);
+                lock(&mapp).reachability.insert(address, reachable);
+            }
+            Ok((_, None)) => (),
+            Err(e) => warn!("Reachability probe panicked: {}", e),
+        }
+    }
+}
+
+/// Sign of well known currencies, other ones are shown by their tickers
+fn currency_symbol(currency: &str) -> &str {
+    match currency {
+        "EUR" => "€",
+        "USD" => "$",
+        "GBP" => "£",
+        _ => currency,
+    }
+}
+
+/// Sub tab of the Channels screen that shows channels in the state
+fn chans_tab_of(state: ChannelState) -> usize {
+    if state.is_normal() {
+        0
+    } else if state.is_pending() {
+        1
+    } else if state.is_sleeping() {
+        2
+    } else {
+        3
+    }
+}
+
+/// Channels that are in both polls and have different local balance. New channels are not
+/// reported, so nothing flashes right after the start.
+fn changed_balances(previous: &[ChannelStats], fresh: &[ChannelStats]) -> Vec<String> {
+    let previous: HashMap<&str, u64> = previous
+        .iter()
+        .map(|c| (c.chan_id.as_str(), c.local))
+        .collect();
+    fresh
+        .iter()
+        .filter(|c| previous.get(c.chan_id.as_str()).is_some_and(|local| *local != c.local))
+        .map(|c| c.chan_id.clone())
+        .collect()
+}
+
+/// Format of keys in the daily fees history
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+/// Records of the fresh audit followed by the known records that are still `recent` and
+/// missing in the fresh one
+fn merge_records<T: Clone, K: Eq + std::hash::Hash>(
+    known: &[T],
+    fresh: Vec<T>,
+    key: impl Fn(&T) -> K,
+    recent: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    let fresh_keys: HashSet<K> = fresh.iter().map(&key).collect();
+    let mut merged = fresh;
+    merged.extend(
+        known
+            .iter()
+            .filter(|r| recent(r) && !fresh_keys.contains(&key(r)))
+            .cloned(),
+    );
+    merged
+}
+
+/// Response of a plugin endpoint. Installed plugin that fails with a server error must not
+/// abort the whole poll, the error is returned along with empty data to show on its tab.
+fn plugin_response<T: Default>(
+    res: Result<T, super::api::Error>,
+) -> Result<(T, Option<String>), super::api::Error> {
+    match res {
+        Ok(data) => Ok((data, None)),
+        Err(e) if e.kind() == ErrorKind::Server => Ok((T::default(), Some(e.to_string()))),
+        Err(e) => Err(e),
+    }
+}
+
 pub async fn query_node_info(mapp: AppMutex) -> Result<(), super::api::Error> {
     trace!("Quering next node stats");
-    let client = mapp.lock().unwrap().client.clone();
-    trace!("Getting channels");
-    let chan_info = client.get_channels().await?;
-    trace!("Getting audit");
-    let audit_info = client.get_audit().await?;
-
-    trace!("Getting nodes for that channels");
-    let channel_nodes: Vec<&str> = chan_info.iter().map(|c| &c.node_id[..]).unique().collect();
-    let nodes_info = client.get_nodes(&channel_nodes).await?;
-
-    let supported = mapp.lock().unwrap().supported.clone();
-    trace!("Getting info about hosted channels");
-    let hosted_chans: HcInfo = if supported.contains(&NodePlugin::HostedChannels) {
-        client.get_hosted_channels().await?
-    } else {
-        HcInfo {
-            channels: HashMap::new(),
+    let client = lock(&mapp).client.clone();
+    let unknown_plugins: Vec<NodePlugin> =
+        lock(&mapp).unknown_plugins.iter().cloned().collect();
+    if !unknown_plugins.is_empty() {
+        trace!("Probing plugins again: {:?}", unknown_plugins);
+        let probe = client.probe_plugins(&unknown_plugins).await;
+        let mut app = lock(&mapp);
+        app.supported.extend(probe.supported);
+        app.unknown_plugins = probe.unknown;
+        app.plugin_errors.extend(probe.erroring);
+        app.build_tabs();
+    }
+
+    trace!("Getting channels, audit and peers");
+    let fetch_started = chrono::offset::Utc::now().timestamp();
+    let (audit_period, request_period) = {
+        let app = lock(&mapp);
+        (app.audit_period(), app.audit_request_period(fetch_started))
+    };
+    let snapshot = client.get_node_snapshot(request_period).await;
+    let chan_info = snapshot.channels?;
+    // Peers are only shown on their tab, the rest of the stats doesn't need them
+    let peers = match snapshot.peers {
+        Ok(peers) => Some(peers),
+        Err(e) => {
+            warn!("Failed to get peers, keeping the previous ones: {}", e);
+            None
         }
     };
-    trace!("Getting info about fiat channels");
-    let fiat_chans: FcInfo = if supported.contains(&NodePlugin::FiatChannels) {
-        client.get_fiat_channels().await?
-    } else {
-        FcInfo {
-            channels: HashMap::new(),
+    // Audit that we cannot parse shouldn't wipe the stats, we keep the previous one
+    let audit_info = match snapshot.audit {
+        Ok(audit) => Some(audit),
+        Err(e) if e.kind() == ErrorKind::Decode => {
+            error!("Failed to parse audit, keeping the previous one: {}", e);
+            lock(&mapp).push_error(format!("Failed to parse audit: {}", e));
+            None
+        }
+        Err(e) => return Err(e),
+    };
+
+    let channel_nodes: Vec<&str> = chan_info
+        .iter()
+        .map(|c| &c.node_id[..])
+        .chain(peers.iter().flatten().map(|p| &p.node_id[..]))
+        .unique()
+        .collect();
+    let now = chrono::offset::Utc::now().timestamp();
+    let stale_nodes: Vec<&str> = {
+        let app = lock(&mapp);
+        channel_nodes
+            .into_iter()
+            .filter(|id| app.is_node_stale(id, now))
+            .collect()
+    };
+    let supported = lock(&mapp).supported.clone();
+
+    trace!("Getting nodes for that channels and peers, hosted and fiat channels");
+    let nodes_request = async {
+        if stale_nodes.is_empty() {
+            Ok(vec![])
+        } else {
+            debug!("Refreshing {} stale nodes", stale_nodes.len());
+            client.get_nodes(&stale_nodes).await
         }
     };
+    let hosted_request = async {
+        if supported.contains(&NodePlugin::HostedChannels) {
+            plugin_response(client.get_hosted_channels().await)
+        } else {
+            Ok((HcInfo::default(), None))
+        }
+    };
+    let fiat_request = async {
+        if supported.contains(&NodePlugin::FiatChannels) {
+            plugin_response(client.get_fiat_channels().await)
+        } else {
+            Ok((FcInfo::default(), None))
+        }
+    };
+    let (nodes_info, (hosted_chans, hosted_error), (fiat_chans, fiat_error)) =
+        tokio::try_join!(nodes_request, hosted_request, fiat_request)?;
+    let stale_nodes: Vec<String> = stale_nodes.into_iter().map(|id| id.to_owned()).collect();
 
     {
         trace!("Start calculation");
-        let mut app = mapp.lock().unwrap();
+        let mut app = lock(&mapp);
 
         app.channels = chan_info;
+        if let Some(peers) = peers {
+            app.peers = peers;
+        }
+        app.peers_selected = app.peers_selected.min(app.peers.len().saturating_sub(1));
         app.hc_channels = hosted_chans.channels;
         app.fc_channels = fiat_chans.channels;
+        app.set_plugin_error(NodePlugin::HostedChannels, hosted_error);
+        app.set_plugin_error(NodePlugin::FiatChannels, fiat_error);
         trace!("Calculating channels activity");
         app.active_chans = app.get_active_chans();
         app.pending_chans = app.get_pending_chans();
         app.sleeping_chans = app.get_sleeping_chans();
+        app.closed_chans = app.get_closed_chans();
         app.active_sats = app.get_active_sats();
         app.pending_sats = app.get_pending_sats();
         app.sleeping_sats = app.get_sleeping_sats();
+        app.median_feerate = app.get_median_feerate();
+        let anomalies = app.get_channel_anomalies();
+        let previous_anomalies = std::mem::replace(&mut app.channel_anomalies, anomalies);
+        app.warn_channel_anomalies(&previous_anomalies);
+        app.warn_dataless_channels();
 
         trace!("Calculating relays amounts");
-        app.audit = audit_info;
-        let (amounts, max_amounts) = app.get_relays_amounts_line();
-        app.relays_amounts_line = amounts;
-        app.relays_maximum_count = max_amounts;
-        trace!("Calculating relays volumes");
-        let (volumes, max_volume) = app.get_relays_volumes_line();
-        app.relays_volumes_line = volumes;
-        app.relays_maximum_volume = max_volume;
-
-        trace!("Calculating relays month");
-        app.relayed_month = app.get_relayed_month();
-        trace!("Calculating relays day");
-        app.relayed_day = app.get_relayed_day();
-        trace!("Calculating relays count month");
-        app.relayed_count_month = app.get_relayed_count_month();
-        trace!("Calculating relays count day");
-        app.relayed_count_day = app.get_relayed_count_day();
-
-        trace!("Calculating fees");
-        app.fee_month = app.get_fee_month();
-        app.fee_day = app.get_fee_day();
+        let mut audit_changed = false;
+        if let Some(audit) = audit_info {
+            audit_changed = app.merge_audit(audit, audit_period);
+            if request_period == audit_period {
+                app.audit_fetched_period = audit_period;
+                app.audit_full_at = fetch_started;
+            }
+            app.audit_fetched_at = fetch_started;
+        }
+        app.warn_insane_relays();
+        if let Err(e) = app.accumulate_relays() {
+            warn!("Failed to accumulate all-time relays: {}", e);
+        }
+        if let Err(e) = app.record_daily_fees() {
+            warn!("Failed to record daily fees: {}", e);
+        }
+        app.update_relays_stats();
+        if audit_changed {
+            if let Err(e) = app.persist_audit() {
+                warn!("Failed to persist audit: {}", e);
+            }
+        }
         trace!("Calculating return rate");
         app.return_rate = app.get_return_rate();
+        app.return_rate_provisional = app.is_return_rate_provisional();
 
-        trace!("Getting map of known nodes");
-        app.known_nodes = nodes_info
-            .iter()
-            .map(|n| (n.node_id.clone(), n.clone()))
-            .collect();
+        trace!("Updating map of known nodes");
+        for id in stale_nodes {
+            app.nodes_fetched.insert(id, now);
+        }
+        app.known_nodes
+            .extend(nodes_info.into_iter().map(|n| (n.node_id.clone(), n)));
         trace!("Calculation of channels stats");
-        app.channels_stats = app.get_channels_stats(app.stats_interval);
-        app.hosted_stats = app.get_hosted_stats();
-        app.fiat_stats = app.get_fiat_stats();
+        let channels_stats = app.get_channels_stats(app.stats_interval);
+        let hosted_stats = app.get_hosted_stats();
+        let fiat_stats = app.get_fiat_stats();
+        let changed = [
+            changed_balances(&app.channels_stats, &channels_stats),
+            changed_balances(&app.hosted_stats, &hosted_stats),
+            changed_balances(&app.fiat_stats, &fiat_stats),
+        ]
+        .concat();
+        app.flash_balance_changes(changed);
+        app.channels_stats = channels_stats;
+        app.hosted_stats = hosted_stats;
+        app.fiat_stats = fiat_stats;
+        app.resync_chans_selection();
         debug!("Fiat channels count {}", app.fiat_stats.len());
+        if let Err(e) = app.record_fiat_rates() {
+            warn!("Failed to record fiat rates: {}", e);
+        }
+        if let Err(e) = app.record_channel_volumes() {
+            warn!("Failed to record channel volumes: {}", e);
+        }
+        if let Err(e) = app.record_channel_fees() {
+            warn!("Failed to record channel fees: {}", e);
+        }
+        if let Err(e) = app.record_channel_states() {
+            warn!("Failed to record channel states: {}", e);
+        }
+        app.last_update = Some(chrono::offset::Utc::now().timestamp());
     }
+    query_incoming_payments(mapp, &client).await;
     trace!("Updating is done");
     Ok(())
 }
+
+/// Poll the node forever, backing off while it doesn't respond
+async fn poll_node(mapp: AppMutex) {
+    let refresh = lock(&mapp).refresh.clone();
+    let mut breaker = CircuitBreaker::new(
+        App::BREAKER_THRESHOLD,
+        App::POLL_PERIOD,
+        App::MAX_RETRY_DELAY,
+    );
+    loop {
+        let manual = std::mem::take(&mut lock(&mapp).reconnect_requested);
+        let res = if manual {
+            breaker.reset();
+            reconnect(mapp.clone()).await
+        } else {
+            query_node_info(mapp.clone()).await
+        };
+        match res {
+            Ok(()) => {
+                breaker.success();
+                let mut app = lock(&mapp);
+                app.connection = ConnectionStatus::Online;
+                app.breaker_open = false;
+                app.next_retry = None;
+                app.publish_stats();
+            }
+            Err(e) => {
+                breaker.failure();
+                let now = chrono::offset::Utc::now().timestamp();
+                // No timestamp in the message, the log tab has it and the popup cooldown
+                // must recognize the repeated failure
+                let estr = format!("App worker failed with: {}", e);
+                error!("{}", estr);
+                let mut app = lock(&mapp);
+                app.connection = ConnectionStatus::from_error(e.kind());
+                if breaker.is_open() && !app.breaker_open {
+                    warn!("{} polls failed in a row, backing off", breaker.failures());
+                }
+                app.breaker_open = breaker.is_open();
+                app.next_retry = Some(now + breaker.delay().as_secs() as i64);
+                app.push_error(estr);
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(breaker.delay()) => (),
+            _ = refresh.notified() => trace!("Refresh is requested"),
+        }
+    }
+}
+
+/// Query the node info again, as the node may have been restarted with another version or
+/// settings, and then poll everything else
+async fn reconnect(mapp: AppMutex) -> Result<(), super::api::Error> {
+    let client = lock(&mapp).client.clone();
+    let node_info = client.get_info().await?;
+    {
+        let mut app = lock(&mapp);
+        app.version_warning = check_version(&node_info);
+        app.client = client.with_quirks(Quirks::of(node_info.parsed_version()));
+        app.node_info = node_info;
+    }
+    query_node_info(mapp).await
+}
+
+/// Get invoices of the latest received payments. Failures don't break the poll, we just
+/// show the payments without descriptions.
+async fn query_incoming_payments(mapp: AppMutex, client: &Client) {
+    let hashes = {
+        let mut app = lock(&mapp);
+        app.prune_incoming_payments();
+        app.incoming_payments_to_query(chrono::offset::Utc::now().timestamp())
+    };
+    let infos = client.get_received_infos(hashes).await;
+    let now = chrono::offset::Utc::now().timestamp();
+    let mut app = lock(&mapp);
+    for (hash, info) in infos {
+        let info = match info {
+            Ok(info) => Some(info),
+            Err(e) if e.status() == Some(404) => {
+                debug!("Node doesn't know received payment {}", hash);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to get received info of {}: {}", hash, e);
+                app.incoming_queried.insert(hash, now);
+                continue;
+            }
+        };
+        if info.as_ref().is_none_or(|i| i.status.is_final()) {
+            app.incoming_queried.remove(&hash);
+        } else {
+            app.incoming_queried.insert(hash.clone(), now);
+        }
+        app.incoming_payments.insert(hash, info);
+    }
+}
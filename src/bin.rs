@@ -1,7 +1,18 @@
+mod amount;
 mod app;
 mod api;
+mod commands;
+mod fifo;
+mod format;
+mod history;
 mod opts;
+mod price;
+mod reachability;
+mod storage;
+mod supervise;
+mod theme;
 mod ui;
+mod valuation;
 
 #[macro_use(defer)]
 extern crate scopeguard;
@@ -10,14 +21,23 @@ use clap::Parser;
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 
-use self::app::App;
+use self::app::{check_version, query_node_info, App};
 use self::api::Client;
 use self::opts::Opts;
-use self::ui::run_ui;
+use self::supervise::lock;
+use self::theme::Theme;
+use self::ui::{restore_terminal, run_ui};
 
 use log::LevelFilter;
 use log4rs::{
-    append::file::FileAppender,
+    append::rolling_file::{
+        policy::compound::{
+            roll::{delete::DeleteRoller, fixed_window::FixedWindowRoller, Roll},
+            trigger::size::SizeTrigger,
+            CompoundPolicy,
+        },
+        RollingFileAppender,
+    },
     config::{Appender, Config, Root},
     encode::pattern::PatternEncoder,
     filter::threshold::ThresholdFilter,
@@ -26,14 +46,39 @@ use log4rs::{
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let opts: Opts = Opts::parse();
+    let (altscreen, focus_events) = (!opts.no_altscreen, opts.refresh_on_focus);
+    supervise::install_panic_hook(move || restore_terminal(altscreen, focus_events));
+    let client = Client::new(&opts.url, "eclair", opts.password.as_deref().unwrap_or_default());
+    let client: Client = match &opts.bearer_token {
+        Some(token) => client.with_bearer_token(token),
+        None => client,
+    }
+    .with_plugins(opts.plugins.clone());
+    if opts.version_check {
+        // Doesn't touch the state database, so it can run next to the running UI
+        let info = client.get_info().await?;
+        match check_version(&info) {
+            None => println!("eclair {} is compatible", info.version),
+            Some(warning) => {
+                println!("{}", warning);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
     let db: sled::Db = sled::open(&opts.state)?;
-    let client: Client = Client::new(&opts.url, "eclair", &opts.password);
 
-    // Logging to log file.
-    let logfile = FileAppender::builder()
+    // Logging to log file that is rotated when it grows too big.
+    let roller: Box<dyn Roll> = if opts.log_keep == 0 {
+        Box::new(DeleteRoller::new())
+    } else {
+        Box::new(FixedWindowRoller::builder().build(&format!("{}.{{}}", opts.logfile), opts.log_keep)?)
+    };
+    let policy = CompoundPolicy::new(Box::new(SizeTrigger::new(opts.log_max_size)), roller);
+    let logfile = RollingFileAppender::builder()
         // Pattern: https://docs.rs/log4rs/*/log4rs/encode/pattern/index.html
         .encoder(Box::new(PatternEncoder::new("{l} - {m}\n")))
-        .build(opts.logfile)
+        .build(&opts.logfile, Box::new(policy))
         .unwrap();
 
     // Log to file with programmatically set level from CLI args
@@ -56,9 +101,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // once you are done.
     let _handle = log4rs::init_config(config)?;
 
-    let app = Arc::new(Mutex::new(App::new(client, db).await?));
+    let theme = opts.theme.or_else(Theme::detect).unwrap_or(Theme::Dark);
+    log::debug!("Using {:?} theme", theme);
+    let app = Arc::new(Mutex::new(App::new(client, db, &opts, theme).await?));
+    if let Some(path) = &opts.dump_state {
+        query_node_info(app.clone()).await?;
+        lock(&app).dump_state(path)?;
+        return Ok(());
+    }
     App::start_workers(app.clone()).await;
-    run_ui(app)?;
+    run_ui(app, altscreen)?;
     // loop {
     //     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     // }
@@ -0,0 +1,189 @@
+use super::api::{route::RouteResponse, Client};
+use super::app::{App, AppMutex};
+use super::supervise::lock;
+
+/// Commands that change state of the node, always confirmed by the user before execution.
+/// Aliases are raw labels of the peers, they are anonymized only when drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    CloseChannel {
+        channel_id: String,
+        node_id: String,
+        alias: String,
+        force: bool,
+    },
+    ConnectPeer {
+        /// `nodeId@host:port`
+        uri: String,
+        alias: String,
+    },
+    DisconnectPeer {
+        node_id: String,
+        alias: String,
+    },
+}
+
+impl Command {
+    /// Question that we ask the user before executing the command, drawn with the current
+    /// anonymization
+    pub fn confirmation(&self, app: &App) -> String {
+        match self {
+            Command::CloseChannel {
+                node_id,
+                alias,
+                force: false,
+                ..
+            } => format!("Close channel with {}?", app.shown_alias(alias, node_id)),
+            Command::CloseChannel {
+                node_id,
+                alias,
+                force: true,
+                ..
+            } => format!(
+                "Force close channel with {}? Funds will be locked until the timeout.",
+                app.shown_alias(alias, node_id)
+            ),
+            Command::ConnectPeer { uri, alias } => {
+                let (node_id, address) = uri.split_once('@').unwrap_or((&uri[..], ""));
+                format!(
+                    "Connect to {} at {}?",
+                    app.shown_alias(alias, node_id),
+                    app.shown_address(address)
+                )
+            }
+            Command::DisconnectPeer { node_id, alias } => format!(
+                "Disconnect from {}? Channels with the peer will go offline.",
+                app.shown_alias(alias, node_id)
+            ),
+        }
+    }
+
+    /// What the command does, for error messages that are anonymized when drawn
+    fn summary(&self) -> String {
+        match self {
+            Command::CloseChannel { alias, force: false, .. } => {
+                format!("Closing channel with {}", alias)
+            }
+            Command::CloseChannel { alias, force: true, .. } => {
+                format!("Force closing channel with {}", alias)
+            }
+            Command::ConnectPeer { alias, .. } => format!("Connecting to {}", alias),
+            Command::DisconnectPeer { alias, .. } => format!("Disconnecting from {}", alias),
+        }
+    }
+}
+
+/// Diagnostic query whether the node is reachable, doesn't change anything on the node so
+/// it is sent without confirmation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteQuery {
+    pub node_id: String,
+    pub amount_msat: u64,
+}
+
+impl std::str::FromStr for RouteQuery {
+    type Err = String;
+
+    /// Parse query in form of `<node id> <amount in sats>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        match (parts.next(), parts.next().map(|a| a.parse::<u64>()), parts.next()) {
+            (Some(node_id), Some(Ok(sats)), None)
+                if node_id.len() == 66 && node_id.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Ok(RouteQuery {
+                    node_id: node_id.to_lowercase(),
+                    amount_msat: sats.saturating_mul(1000),
+                })
+            }
+            _ => Err(format!("Expected `<node id> <amount in sats>`, got '{}'", s.trim())),
+        }
+    }
+}
+
+/// Query routes in background and show the best one or that there is no route
+pub fn find_route(mapp: AppMutex, client: Client, query: RouteQuery) {
+    tokio::spawn(async move {
+        let res = client.find_route(&query.node_id, query.amount_msat).await;
+        let mut app = lock(&mapp);
+        let alias = app.get_peer_alias(&query.node_id);
+        let amount = app.amount_unit.format(query.amount_msat);
+        match res {
+            Ok(response) => {
+                let description = describe_route(&app, &alias, &amount, &response);
+                app.command_result = Some(description);
+            }
+            Err(e) if e.status() == Some(400) => {
+                app.command_result = Some(format!("No route to {} for {}", alias, amount))
+            }
+            Err(e) => app.push_error(format!("Failed to find route to {}: {}", alias, e)),
+        }
+    });
+}
+
+fn describe_route(app: &App, alias: &str, amount: &str, response: &RouteResponse) -> String {
+    let route = match response.routes.first() {
+        Some(route) => route,
+        None => return format!("No route to {} for {}", alias, amount),
+    };
+    let mut lines = vec![format!(
+        "Route to {} for {}, {} hops:",
+        alias,
+        amount,
+        route.node_ids.len().saturating_sub(1)
+    )];
+    for (i, node_id) in route.node_ids.iter().enumerate() {
+        let name = if *node_id == app.node_info.node_id {
+            format!("{} (this node)", app.node_info.alias)
+        } else {
+            app.get_peer_alias(node_id)
+        };
+        lines.push(format!("{}. {}", i, name));
+    }
+    if response.routes.len() > 1 {
+        lines.push(format!("{} more routes found", response.routes.len() - 1));
+    }
+    lines.join("\n")
+}
+
+/// Run the command in background, show the result and refresh node stats after it
+pub fn execute(mapp: AppMutex, client: Client, command: Command) {
+    tokio::spawn(async move {
+        let res = run(&client, &command).await;
+        let mut app = lock(&mapp);
+        match res {
+            Ok(msg) => app.command_result = Some(msg),
+            Err(e) => app.push_error(format!("{} failed: {}", command.summary(), e)),
+        }
+        app.refresh.notify_one();
+    });
+}
+
+#[cfg(feature = "commands")]
+async fn run(client: &Client, command: &Command) -> Result<String, String> {
+    use itertools::Itertools;
+    match command {
+        Command::CloseChannel {
+            channel_id,
+            alias,
+            force,
+            ..
+        } => {
+            let res = client
+                .close_channel(channel_id, *force)
+                .await
+                .map_err(|e| e.to_string())?;
+            // Only the one channel is closed, the alias tells which one
+            Ok(res.values().map(|r| format!("{}: {}", alias, r)).join("\n"))
+        }
+        Command::ConnectPeer { uri, .. } => client.connect(uri).await.map_err(|e| e.to_string()),
+        Command::DisconnectPeer { node_id, .. } => {
+            client.disconnect(node_id).await.map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(not(feature = "commands"))]
+async fn run(_client: &Client, _command: &Command) -> Result<String, String> {
+    Err("eclair-tortoise is built without commands support".to_owned())
+}
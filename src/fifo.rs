@@ -0,0 +1,67 @@
+//! Stream JSON lines with stats to a named pipe for external consumers
+use log::*;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// Latest line that is not yet written to the pipe
+type Slot = Arc<(Mutex<Option<String>>, Condvar)>;
+
+/// Writer of the `--stats-fifo` pipe. Opening a FIFO blocks until somebody reads it and a
+/// write blocks while the reader is stalled, so the writing happens in own thread and the
+/// worker only replaces the pending line. Lines that were not read in time are dropped,
+/// the reader always gets the freshest stats.
+pub struct StatsFifo {
+    slot: Slot,
+}
+
+impl StatsFifo {
+    pub fn spawn(path: &str) -> Self {
+        let slot: Slot = Arc::new((Mutex::new(None), Condvar::new()));
+        let writer_slot = slot.clone();
+        let path = path.to_owned();
+        thread::spawn(move || write_lines(&path, &writer_slot));
+        StatsFifo { slot }
+    }
+
+    /// Queue the line for writing, replacing the line that the reader didn't take yet
+    pub fn publish(&self, line: String) {
+        let (pending, ready) = &*self.slot;
+        *pending.lock().unwrap() = Some(line);
+        ready.notify_one();
+    }
+}
+
+fn write_lines(path: &str, slot: &Slot) {
+    let (pending, ready) = &**slot;
+    let mut fifo: Option<File> = None;
+    loop {
+        let mut line = {
+            let mut guard = ready
+                .wait_while(pending.lock().unwrap(), |line| line.is_none())
+                .unwrap();
+            guard.take().unwrap()
+        };
+        if fifo.is_none() {
+            // Blocks until a reader opens the other end
+            match OpenOptions::new().write(true).open(path) {
+                Ok(file) => fifo = Some(file),
+                Err(e) => {
+                    warn!("Failed to open stats fifo {}: {}", path, e);
+                    continue;
+                }
+            }
+            // Stats may have been updated while we were waiting for the reader
+            if let Some(fresh) = pending.lock().unwrap().take() {
+                line = fresh;
+            }
+        }
+        if let Some(file) = fifo.as_mut() {
+            if let Err(e) = writeln!(file, "{}", line) {
+                debug!("Reader of stats fifo {} is gone: {}", path, e);
+                fifo = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Formatting of values for display
+use num_format::{Locale, ToFormattedString};
+
+/// Time zone in which we display timestamps, calculations are always done in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayTz {
+    Utc,
+    /// Time zone of the system
+    Local,
+    Fixed(chrono::FixedOffset),
+}
+
+impl std::str::FromStr for DisplayTz {
+    type Err = String;
+
+    /// Parse `utc`, `local` or offset like `+03:00`, `-0530` or `+3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("expected utc, local or offset like +03:00, got `{}`", s);
+        match &s.to_lowercase()[..] {
+            "utc" => return Ok(DisplayTz::Utc),
+            "local" => return Ok(DisplayTz::Local),
+            _ => (),
+        }
+        let (sign, rest) = match s.chars().next() {
+            Some('+') => (1, &s[1..]),
+            Some('-') => (-1, &s[1..]),
+            _ => return Err(err()),
+        };
+        let digits: String = rest.chars().filter(|c| *c != ':').collect();
+        // Only ASCII digits are left, so the slicing below is on char boundaries
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(err());
+        }
+        let (hours, minutes) = match digits.len() {
+            1 | 2 => (digits.parse::<i32>().map_err(|_| err())?, 0),
+            4 => (
+                digits[..2].parse::<i32>().map_err(|_| err())?,
+                digits[2..].parse::<i32>().map_err(|_| err())?,
+            ),
+            _ => return Err(err()),
+        };
+        // Real offsets range from -12:00 to +14:00
+        if hours > 14 || minutes > 59 {
+            return Err(err());
+        }
+        Ok(DisplayTz::Fixed(chrono::FixedOffset::east(
+            sign * (hours * 3600 + minutes * 60),
+        )))
+    }
+}
+
+impl DisplayTz {
+    /// Format the unix timestamp in the time zone
+    pub fn format(self, unix: i64, fmt: &str) -> String {
+        use chrono::TimeZone;
+        match self {
+            DisplayTz::Utc => chrono::Utc.timestamp(unix, 0).format(fmt).to_string(),
+            DisplayTz::Local => chrono::Local.timestamp(unix, 0).format(fmt).to_string(),
+            DisplayTz::Fixed(offset) => offset.timestamp(unix, 0).format(fmt).to_string(),
+        }
+    }
+}
+
+/// Abbreviate a number with a metric suffix: `999`, `1.0k`, `340k`, `1.5M`. Values below 100
+/// of the suffix keep one decimal place.
+pub fn abbreviate(value: u64) -> String {
+    const SUFFIXES: [(&str, u64); 4] = [
+        ("k", 1_000),
+        ("M", 1_000_000),
+        ("G", 1_000_000_000),
+        ("T", 1_000_000_000_000),
+    ];
+    if value < 1000 {
+        return value.to_string();
+    }
+    for (i, (suffix, divisor)) in SUFFIXES.iter().enumerate() {
+        let scaled = value as f64 / *divisor as f64;
+        // Rounding would give `1000k`, move to the next suffix instead
+        if scaled >= 999.5 && i + 1 < SUFFIXES.len() {
+            continue;
+        }
+        return if scaled < 99.95 {
+            format!("{:.1}{}", scaled, suffix)
+        } else {
+            format!("{:.0}{}", scaled, suffix)
+        };
+    }
+    unreachable!()
+}
+
+/// Unit in which amounts are displayed, cycled with `u`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountUnit {
+    Sat,
+    /// 100 sats
+    Bits,
+    /// 100,000 sats
+    MilliBtc,
+    Btc,
+}
+
+impl AmountUnit {
+    pub fn next(self) -> Self {
+        match self {
+            AmountUnit::Sat => AmountUnit::Bits,
+            AmountUnit::Bits => AmountUnit::MilliBtc,
+            AmountUnit::MilliBtc => AmountUnit::Btc,
+            AmountUnit::Btc => AmountUnit::Sat,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AmountUnit::Sat => "sats",
+            AmountUnit::Bits => "bits",
+            AmountUnit::MilliBtc => "mBTC",
+            AmountUnit::Btc => "BTC",
+        }
+    }
+
+    /// Msats in one unit and amount of decimal places that we show, each place is
+    /// at least a sat
+    fn scale(self) -> (u64, u32) {
+        match self {
+            AmountUnit::Sat => (1_000, 0),
+            AmountUnit::Bits => (100_000, 2),
+            AmountUnit::MilliBtc => (100_000_000, 5),
+            AmountUnit::Btc => (100_000_000_000, 8),
+        }
+    }
+
+    /// Format msat amount in the unit with thousands separators, e.g. `1,234.56 bits`.
+    /// Fraction is truncated to whole sats.
+    pub fn format(self, msat: u64) -> String {
+        let (msats, decimals) = self.scale();
+        let whole = (msat / msats).to_formatted_string(&Locale::en);
+        if decimals == 0 {
+            return format!("{} {}", whole, self.label());
+        }
+        let fraction = (msat % msats) / (msats / 10u64.pow(decimals));
+        format!(
+            "{}.{:0width$} {}",
+            whole,
+            fraction,
+            self.label(),
+            width = decimals as usize
+        )
+    }
+
+    /// Format msat amount for narrow widgets. Sats are abbreviated like `1.2M sats`, other
+    /// units are already short enough.
+    pub fn format_short(self, msat: u64) -> String {
+        match self {
+            AmountUnit::Sat => format!("{} {}", abbreviate(msat / 1000), self.label()),
+            _ => self.format(msat),
+        }
+    }
+}
+
+/// Scale sparkline values to percents of the maximum, logarithmically if `log_scale` so small
+/// values stay visible near outliers. Empty when all values are zero.
+pub fn scale_line(values: &[u64], log_scale: bool) -> Vec<u64> {
+    let max = values.iter().copied().max().unwrap_or(0);
+    if max == 0 {
+        return vec![];
+    }
+    let scale = |a: u64| {
+        if log_scale {
+            (a as f64).ln_1p() / (max as f64).ln_1p()
+        } else {
+            a as f64 / max as f64
+        }
+    };
+    values.iter().map(|a| (100.0 * scale(*a)) as u64).collect()
+}
+
+/// Title of a 24h volumes sparkline with its scale and maximum, e.g.
+/// `24h relay volumes, log scale (max: 1,234 sats)`
+pub fn volumes_title(name: &str, log_scale: bool, max_msat: u64) -> String {
+    format!(
+        "24h {}, {} (max: {} sats)",
+        name,
+        if log_scale { "log scale" } else { "linear" },
+        (max_msat / 1000).to_formatted_string(&Locale::en)
+    )
+}
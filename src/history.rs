@@ -0,0 +1,43 @@
+//! Durations that we derive from recorded histories of channel states
+use crate::api::channel::ChannelState;
+
+/// Record in a history
+pub type Snapshot<T> = (i64, T);
+
+/// States in which the channel can't relay because the peer is away
+pub const OFFLINE_STATES: [ChannelState; 2] = [ChannelState::Offline, ChannelState::Syncing];
+
+/// When the channel entered any of the states without a break, `None` if it is in another
+/// state now. Histories keep only recent changes, so a channel that flaps between the states
+/// may have lost the start of its run. In that case `known_start`, that is remembered apart
+/// from the history, is used when it is earlier.
+pub fn run_start(
+    history: &[Snapshot<ChannelState>],
+    states: &[ChannelState],
+    known_start: Option<i64>,
+) -> Option<i64> {
+    let run = history
+        .iter()
+        .rev()
+        .take_while(|(_, state)| states.contains(state))
+        .count();
+    if run == 0 {
+        return None;
+    }
+    let kept_start = history[history.len() - run].0;
+    // A record in another state before the run shows where it began
+    if run < history.len() {
+        return Some(kept_start);
+    }
+    Some(known_start.map_or(kept_start, |start| start.min(kept_start)))
+}
+
+/// How long the channel is in any of the states without a break in seconds, see `run_start`
+pub fn duration_in(
+    history: &[Snapshot<ChannelState>],
+    states: &[ChannelState],
+    known_start: Option<i64>,
+    now: i64,
+) -> Option<i64> {
+    run_start(history, states, known_start).map(|start| now - start)
+}
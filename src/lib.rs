@@ -1 +1,7 @@
+pub mod amount;
 pub mod api;
+pub mod format;
+pub mod history;
+pub mod supervise;
+pub mod theme;
+pub mod valuation;
@@ -1,19 +1,41 @@
 use clap::{crate_version, Parser};
 
+use super::api::NodePlugin;
+use super::app::Tab;
+use super::format::DisplayTz;
+use super::theme::Theme;
+use super::valuation::FiatValuation;
+
 #[derive(Parser)]
 #[clap(version=crate_version!(), author="NCrashed <ncrashed@protonmail.com>")]
 pub struct Opts {
-    /// The full url of the lightning node API that we monitor.
-    #[clap(short, long, default_value = "http://127.0.0.1:8080")]
+    /// The full url of the lightning node API that we monitor. Use `unix:///path/to/socket`
+    /// to connect to the API over unix domain socket.
+    #[clap(short, long, default_value = "http://127.0.0.1:8080", env = "ECLAIR_TORTOISE_URL")]
     pub url: String,
 
     /// The password of API for the lightning node. Note that you SHOULD always use
     /// the option to pass it via the environment variable, not directly via the CLI argument.
-    #[clap(long, env = "ECLAIR_TORTOISE_API_PASSWORD", hide_env_values = true)]
-    pub password: String,
+    #[clap(
+        long,
+        env = "ECLAIR_TORTOISE_API_PASSWORD",
+        hide_env_values = true,
+        required_unless_present = "bearer-token"
+    )]
+    pub password: Option<String>,
+
+    /// Token sent as `Authorization: Bearer <token>` instead of the basic auth with the
+    /// password, for auth gateways in front of the node. Pass it via the environment variable.
+    #[clap(
+        long,
+        env = "ECLAIR_TORTOISE_BEARER_TOKEN",
+        hide_env_values = true,
+        conflicts_with = "password"
+    )]
+    pub bearer_token: Option<String>,
 
     /// Path to the local state database directory. Require read-write access.
-    #[clap(short, long, default_value = "./tortoise.db")]
+    #[clap(short, long, default_value = "./tortoise.db", env = "ECLAIR_TORTOISE_STATE")]
     pub state: String,
 
     /// Logging level for putting messages into the log file.
@@ -21,6 +43,164 @@ pub struct Opts {
     pub level: log::LevelFilter,
 
     /// Location of log file to write to
-    #[clap(long, default_value = "./eclair-tortoise.log")]
+    #[clap(long, default_value = "./eclair-tortoise.log", env = "ECLAIR_TORTOISE_LOGFILE")]
     pub logfile: String,
+
+    /// Size of log file in bytes after which it is rotated
+    #[clap(long, default_value = "10485760")]
+    pub log_max_size: u64,
+
+    /// How many rotated log files to keep. Zero means that rotated logs are deleted.
+    #[clap(long, default_value = "5")]
+    pub log_keep: u32,
+
+    /// Allow commands that change state of the node, e.g. closing channels. Each command
+    /// still asks for confirmation before execution.
+    #[clap(long)]
+    pub enable_commands: bool,
+    /// Relays with fee above this fraction of the incoming amount are considered malformed
+    /// and excluded from the stats.
+    #[clap(long, default_value = "0.05")]
+    pub max_fee_ratio: f64,
+
+    /// Channels with spendable balance above the reserve less than this amount of sats are
+    /// highlighted, as they can hardly route anything.
+    #[clap(long, default_value = "50000")]
+    pub reserve_buffer: u64,
+
+    /// Additional eclair plugin to detect, given as `name=method` where `method` is an API
+    /// method that the plugin adds. Can be repeated.
+    #[clap(long = "plugin")]
+    pub plugins: Vec<NodePlugin>,
+
+    /// Query the version of the node, print whether it is compatible and exit. Exit code is
+    /// non-zero if the version is not in the tested range.
+    #[clap(long)]
+    pub version_check: bool,
+
+    /// Query the node once, write parsed data and computed stats as JSON to the file and
+    /// exit. Useful to attach to bug reports, the dump doesn't contain the password.
+    #[clap(long)]
+    pub dump_state: Option<String>,
+
+    /// Named pipe (see mkfifo) to which a JSON line with Dashboard stats is written after
+    /// each poll. Nothing is written while no one reads the pipe.
+    #[clap(long)]
+    pub stats_fifo: Option<String>,
+
+    /// Amount of buckets that the 24h relays sparklines are split into. By default each
+    /// column of the terminal is a bucket.
+    #[clap(long)]
+    pub sparkline_buckets: Option<u64>,
+
+    /// Show full amounts in compact channel widgets instead of abbreviated ones like `1.2M`
+    #[clap(long)]
+    pub full_amounts: bool,
+
+    /// Mask node ids, aliases and addresses with stable labels and round balances, e.g. to
+    /// share screenshots. Toggled by `A`.
+    #[clap(long)]
+    pub anonymize: bool,
+
+    /// Hide balances entirely instead of rounding them in the anonymized mode
+    #[clap(long)]
+    pub anonymize_hide_balances: bool,
+
+    /// Time zone of displayed times: `utc`, `local` for the system time zone or an offset
+    /// like `+03:00`. Buckets are still cut in UTC: daily fees start at UTC midnight, and
+    /// hourly relays at UTC hours, which differ for offsets like `+05:30`.
+    #[clap(long, default_value = "utc")]
+    pub timezone: DisplayTz,
+
+    /// Tab that is shown at startup, e.g. `fiat` or `channels`
+    #[clap(long)]
+    pub start_tab: Option<Tab>,
+
+    /// Colors for `dark` or `light` terminal background. By default the background is
+    /// detected from the `COLORFGBG` variable, falling back to dark.
+    #[clap(long)]
+    pub theme: Option<Theme>,
+
+    /// JSON file with an object that maps node ids to labels, the labels replace gossip
+    /// aliases of the peers, e.g. `{"03864e...": "my exchange"}`
+    #[clap(long)]
+    pub alias_map: Option<String>,
+
+    /// File that the channels leaderboard is written to with `w` on the Channels screen.
+    /// Written as JSON if the name ends with `.json`, as CSV otherwise.
+    #[clap(long, default_value = "./tortoise-leaderboard.csv")]
+    pub leaderboard_path: String,
+
+    /// Periodically try TCP connections to advertised addresses of the node and its peers
+    /// and mark them as reachable or not. Onion addresses are not checked.
+    #[clap(long)]
+    pub check_reachability: bool,
+
+    /// URL that returns JSON with the BTC price to show fiat equivalents of amounts on the
+    /// dashboard, e.g. `https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd`
+    #[clap(long)]
+    pub price_url: Option<String>,
+
+    /// JSON pointer to the price in the response of `--price-url`
+    #[clap(long, default_value = "/bitcoin/usd")]
+    pub price_pointer: String,
+
+    /// Name of the currency of `--price-url` to display
+    #[clap(long, default_value = "USD")]
+    pub price_currency: String,
+
+    /// How balances of fiat channels are valued: `channel` at the rate of each channel or
+    /// `oracle` at the BTC price of `--price-url` for channels of `--price-currency`
+    #[clap(long, default_value = "channel")]
+    pub fiat_valuation: FiatValuation,
+
+    /// Decimal places of fiat amounts, both of fiat channels and of `--price-url` equivalents
+    #[clap(long, default_value = "2")]
+    pub fiat_decimals: usize,
+
+    /// Draw the UI in the current screen instead of the alternate one. Keeps stray output
+    /// and panic messages visible after exit.
+    #[clap(long)]
+    pub no_altscreen: bool,
+
+    /// Channels that are offline or syncing for more days are highlighted as zombies
+    #[clap(long, default_value = "14")]
+    pub zombie_days: i64,
+
+    /// Channels that are syncing or wait for the funding confirmation for more hours are
+    /// flagged as stuck, they likely need the peer to be reconnected
+    #[clap(long, default_value = "6")]
+    pub stuck_hours: i64,
+
+    /// Query the node immediately when the terminal window gets focus back. Requires a
+    /// terminal that reports focus events.
+    #[clap(long)]
+    pub refresh_on_focus: bool,
+
+    /// Panic in the first draw to check that the terminal is restored after a crash
+    #[clap(long, hidden = true)]
+    pub debug_panic: bool,
+
+    /// Close the errors popup automatically after this many seconds without new errors. By
+    /// default, or if it's 0, the popup stays until Enter is pressed.
+    #[clap(long)]
+    pub errors_timeout: Option<u32>,
+
+    /// Seconds during which a dismissed error doesn't show the popup again if it repeats.
+    /// Repeated errors are still written to the log tab.
+    #[clap(long, default_value = "60")]
+    pub errors_cooldown: i64,
+
+    /// Maximum amount of errors in the popup, the oldest ones are dropped from it during
+    /// long outages. The log tab still keeps the latest 100 errors.
+    #[clap(long, default_value = "50")]
+    pub max_errors: usize,
+
+    /// Don't show the Hosted tab even if the node supports hosted channels. Toggled by `H`.
+    #[clap(long)]
+    pub hide_hosted: bool,
+
+    /// Don't show the Fiat tab even if the node supports fiat channels. Toggled by `F`.
+    #[clap(long)]
+    pub hide_fiat: bool,
 }
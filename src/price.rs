@@ -0,0 +1,54 @@
+//! Reference price of BTC from an external source that we show next to sat amounts
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Requesting price source error: {0}")]
+    ReqwestErr(#[from] reqwest::Error),
+    #[error("Failed to decode price: {0}")]
+    DecodingErr(#[from] serde_json::Error),
+    #[error("No price at {0} in the response")]
+    MissingPrice(String),
+}
+
+/// Alias for a `Result` with the error type `self::Error`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP endpoint that returns JSON with price of 1 BTC somewhere inside
+#[derive(Clone)]
+pub struct PriceSource {
+    client: reqwest::Client,
+    url: String,
+    /// JSON pointer to the price in the response, e.g. `/bitcoin/usd`
+    pointer: String,
+}
+
+impl PriceSource {
+    pub fn new(url: &str, pointer: &str) -> Self {
+        PriceSource {
+            client: reqwest::Client::new(),
+            url: url.to_owned(),
+            pointer: pointer.to_owned(),
+        }
+    }
+
+    /// Price of 1 BTC in the reference currency
+    pub async fn fetch(&self) -> Result<f64> {
+        let txt = self
+            .client
+            .get(&self.url)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let json: serde_json::Value = serde_json::from_str(&txt)?;
+        json.pointer(&self.pointer)
+            .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+            .ok_or_else(|| Error::MissingPrice(self.pointer.clone()))
+    }
+}
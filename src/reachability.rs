@@ -0,0 +1,18 @@
+//! Check that advertised addresses of nodes accept connections
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Try to open TCP connection to the address in form of `host:port`. None if we can't check
+/// the address, e.g. onion addresses need Tor.
+pub async fn probe(address: &str) -> Option<bool> {
+    let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+    if host.ends_with(".onion") {
+        return None;
+    }
+    let connected = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address))
+        .await
+        .is_ok_and(|res| res.is_ok());
+    Some(connected)
+}
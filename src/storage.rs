@@ -0,0 +1,104 @@
+//! Histories of values that we keep in the local state database between runs
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Local database error: {0}")]
+    SledErr(#[from] sled::Error),
+    #[error("Failed to encode or decode stored value: {0}")]
+    JsonErr(#[from] serde_json::Error),
+}
+
+/// Alias for a `Result` with the error type `self::Error`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Sled tree with history of fiat channels rates
+pub const FIAT_RATES_TREE: &str = "fiat_rates";
+
+/// Sled tree with history of relayed volumes of each channel
+pub const CHANNEL_VOLUMES_TREE: &str = "channel_volumes";
+
+/// Sled tree with fees earned by each channel per day, values are maps from UTC dates
+pub const CHANNEL_DAILY_FEES_TREE: &str = "channel_daily_fees";
+
+/// Sled tree with history of states of each channel
+pub const STATE_HISTORY_TREE: &str = "state_history";
+
+/// Sled tree with timestamps when channels went offline, the state history may be too short
+/// to keep them for channels that flap between offline and syncing
+pub const SLEEPING_SINCE_TREE: &str = "sleeping_since";
+
+/// Sled tree with states of channels and timestamps when channels entered them, kept by
+/// earlier versions and only read once to seed the state histories
+pub const STATE_SINCE_TREE: &str = "state_since";
+
+/// Sled tree with fees earned per day, keyed by UTC date
+pub const DAILY_FEES_TREE: &str = "daily_fees";
+
+/// Sled tree with channels pinned to the top of lists, values are pin timestamps
+pub const PINNED_TREE: &str = "pinned";
+
+/// Sled tree with payment hashes of relays that are added to the all-time totals, values are
+/// relay timestamps
+pub const SEEN_RELAYS_TREE: &str = "seen_relays";
+
+/// Sled tree with the all-time relay totals
+pub const RELAYS_LIFETIME_TREE: &str = "relays_lifetime";
+pub const RELAYS_LIFETIME_KEY: &str = "relays_lifetime";
+
+/// Sled tree with the last fetched audit
+pub const AUDIT_TREE: &str = "audit";
+pub const AUDIT_KEY: &str = "audit";
+
+pub use super::history::Snapshot;
+
+/// Load full history stored with the key, oldest records first
+pub fn load_history<T: DeserializeOwned>(tree: &sled::Tree, key: &str) -> Result<Vec<Snapshot<T>>> {
+    Ok(load_value(tree, key)?.unwrap_or_default())
+}
+
+/// Append the value to the history if it differs from the last recorded one. Keep only
+/// `limit` most recent records.
+pub fn push_changed<T: Serialize + DeserializeOwned + PartialEq>(
+    tree: &sled::Tree,
+    key: &str,
+    value: T,
+    limit: usize,
+) -> Result<()> {
+    let mut history: Vec<Snapshot<T>> = load_history(tree, key)?;
+    if history.last().is_some_and(|(_, last)| *last == value) {
+        return Ok(());
+    }
+    history.push((chrono::offset::Utc::now().timestamp(), value));
+    if history.len() > limit {
+        history.drain(..history.len() - limit);
+    }
+    tree.insert(key, serde_json::to_vec(&history)?)?;
+    Ok(())
+}
+
+/// Load all values of the tree stored with `store_value`
+pub fn load_values<T: DeserializeOwned>(tree: &sled::Tree) -> Result<Vec<(String, T)>> {
+    tree.iter()
+        .map(|item| {
+            let (key, bytes) = item?;
+            Ok((
+                String::from_utf8_lossy(&key).into_owned(),
+                serde_json::from_slice(&bytes)?,
+            ))
+        })
+        .collect()
+}
+
+pub fn load_value<T: DeserializeOwned>(tree: &sled::Tree, key: &str) -> Result<Option<T>> {
+    match tree.get(key)? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn store_value<T: Serialize>(tree: &sled::Tree, key: &str, value: &T) -> Result<()> {
+    tree.insert(key, serde_json::to_vec(value)?)?;
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! Keep the monitor running and the terminal usable when some code panics
+use log::*;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Name of the thread that owns the terminal. `#[tokio::main]` runs `main` and the UI loop
+/// on the process main thread, tokio workers are named `tokio-runtime-worker`.
+pub const UI_THREAD: &str = "main";
+
+/// Whether the current thread is the one that draws the UI
+pub fn is_ui_thread() -> bool {
+    thread::current().name() == Some(UI_THREAD)
+}
+
+/// Install a panic hook that tells panics of the UI thread from panics of the workers.
+///
+/// A panic of the UI thread calls `restore` before printing the message, otherwise it is
+/// lost in the alternate screen and the shell is left in raw mode, and exits: `defer!` in
+/// `run_ui` can't be relied on when the panic happens outside of it. Panics of other threads
+/// are only logged, printing them would garble the screen, and unwind into their
+/// `JoinHandle` so the watchdog can restart the task.
+pub fn install_panic_hook<F>(restore: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if is_ui_thread() {
+            restore();
+            error!("Panic: {}", info);
+            default_hook(info);
+            std::process::exit(101);
+        }
+        error!("Panic in thread {}: {}", thread::current().name().unwrap_or("<unnamed>"), info);
+    }));
+}
+
+/// Run the task made by `spawn` forever. When it returns or panics, `on_restart` gets the
+/// reason and the task is spawned again after `delay`.
+pub async fn restart_on_exit<F, Fut, R>(mut spawn: F, delay: Duration, mut on_restart: R)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+    R: FnMut(String),
+{
+    loop {
+        let reason = match tokio::spawn(spawn()).await {
+            Ok(()) => "stopped".to_owned(),
+            Err(e) if e.is_panic() => "panicked".to_owned(),
+            Err(e) => format!("failed: {}", e),
+        };
+        on_restart(reason);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Lock the mutex even if a panicked task poisoned it. Restarted tasks and the UI keep
+/// working with the state as the panicked task left it, that's better than crashing too.
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
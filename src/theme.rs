@@ -0,0 +1,76 @@
+//! Base colors of the UI for dark and light terminals
+use tui::style::{Color, Style};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// Guess the theme from the background of the terminal, None if the terminal doesn't
+    /// tell it
+    pub fn detect() -> Option<Theme> {
+        std::env::var("COLORFGBG").ok().and_then(|v| Theme::from_colorfgbg(&v))
+    }
+
+    /// Parse `COLORFGBG` that some terminals (rxvt, Konsole, iTerm2) set to `fg;bg` or
+    /// `fg;default;bg` with ANSI color indices. White, bright white, bright yellow and bright
+    /// cyan backgrounds are light, the rest are too dark for black text.
+    pub fn from_colorfgbg(value: &str) -> Option<Theme> {
+        match value.rsplit(';').next()?.trim().parse::<u8>().ok()? {
+            7 | 11 | 14 | 15 => Some(Theme::Light),
+            _ => Some(Theme::Dark),
+        }
+    }
+
+    /// Background of the whole screen
+    pub fn bg(self) -> Color {
+        match self {
+            Theme::Dark => Color::Black,
+            Theme::Light => Color::White,
+        }
+    }
+
+    /// Color of the main text, e.g. aliases
+    pub fn fg(self) -> Color {
+        match self {
+            Theme::Dark => Color::White,
+            Theme::Light => Color::Black,
+        }
+    }
+
+    /// Base style of screens and popups
+    pub fn style(self) -> Style {
+        Style::default().bg(self.bg()).fg(self.fg())
+    }
+
+    /// Color of secondary text, e.g. labels and timestamps. It must stay readable on the
+    /// selected row too.
+    pub fn secondary(self) -> Color {
+        match self {
+            Theme::Dark => Color::Gray,
+            Theme::Light => Color::DarkGray,
+        }
+    }
+
+    /// Background of the selected row of lists
+    pub fn selected_bg(self) -> Color {
+        match self {
+            Theme::Dark => Color::DarkGray,
+            Theme::Light => Color::Gray,
+        }
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(format!("expected dark or light, got `{}`", s)),
+        }
+    }
+}
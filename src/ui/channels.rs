@@ -4,39 +4,25 @@ use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Paragraph, Tabs},
+    widgets::{Block, Borders, Paragraph, Sparkline, Tabs},
     Frame,
 };
 use std::iter;
 
-use crate::app::{App, ChannelStats};
-use crate::api::{
-    channel::{ChannelInfo, ChannelState},
-};
+use crate::app::{App, ChannelSort, ChannelStats, PeerStats};
 
 pub fn draw_channels<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-
-    let hchunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(33),
-                Constraint::Percentage(66),
-            ]
-            .as_ref(),
-        )
-        .split(area);
-
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
                 Constraint::Length(3),
                 Constraint::Min(1),
+                Constraint::Length(12),
             ]
             .as_ref(),
         )
-        .split(hchunks[0]);
+        .split(area);
 
     let titles = vec![
         Spans::from(vec![
@@ -51,7 +37,11 @@ pub fn draw_channels<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         Spans::from(vec![
             Span::styled("S", Style::default().fg(Color::Yellow)),
             Span::styled("leeping", Style::default().fg(Color::Green)),
-        ])
+        ]),
+        Spans::from(vec![
+            Span::styled("C", Style::default().fg(Color::Yellow)),
+            Span::styled("losed", Style::default().fg(Color::Green)),
+        ]),
     ];
     let tabs = Tabs::new(titles)
         .block(Block::default().borders(Borders::ALL).title("Tabs"))
@@ -60,92 +50,237 @@ pub fn draw_channels<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .highlight_style(
             Style::default()
                 .add_modifier(Modifier::BOLD)
-                .bg(Color::Black),
+                .bg(app.theme.bg()),
         );
     f.render_widget(tabs, vchunks[0]);
-    match app.chans_tab {
-        0 => draw_active_chans(f, app, vchunks[1]),
-        1 => draw_closing_chans(f, app, vchunks[1]),
-        2 => draw_sleeping_chans(f, app, vchunks[1]),
-        _ => (),
-    }
+    let title = match app.chans_tab {
+        0 => "Active",
+        1 => "Pending",
+        2 => "Sleeping",
+        _ => "Closed",
+    };
+    let title = if app.chans_grouped {
+        format!("{} by peer", title)
+    } else if app.chans_sort == ChannelSort::Velocity {
+        format!("{} by turnover", title)
+    } else {
+        title.to_owned()
+    };
+    let hidden = app.get_chans_tab_hidden();
+    let title = if hidden > 0 {
+        format!("{} ({} idle hidden)", title, hidden)
+    } else {
+        title
+    };
+    let stuck = app.stuck_channels().len();
+    let title = if stuck > 0 {
+        format!("{} (⚠ {} stuck)", title, stuck)
+    } else {
+        title
+    };
+    let anomalies = app.channel_anomalies.len();
+    let title = if anomalies > 0 {
+        format!("{} (⚠ {} anomalies)", title, anomalies)
+    } else {
+        title
+    };
+    draw_chans_list(f, app, vchunks[1], &title);
+
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(40), Constraint::Min(0)].as_ref())
+        .split(vchunks[2]);
+    draw_capacity_stats(f, app, bottom[0]);
+    super::draw_bar_chart(
+        f,
+        bottom[1],
+        "Active channels by capacity, sats",
+        &app.capacity_histogram(),
+        Color::Blue,
+    );
+}
+
+fn draw_capacity_stats<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let below_reserve = app.channels_below_reserve().len();
+    let title = if below_reserve > 0 {
+        format!("Active channels capacity ({} near reserve)", below_reserve)
+    } else {
+        "Active channels capacity".to_owned()
+    };
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let lines = match app.get_capacity_stats() {
+        Some(stats) => {
+            let stat = |name: &str, value: u64| {
+                Spans::from(vec![
+                    Span::from(format!("{:<8}", name)),
+                    Span::styled(
+                        app.format_balance(value),
+                        Style::default().fg(Color::Green),
+                    ),
+                ])
+            };
+            vec![
+                stat("Mean:", stats.mean),
+                stat("Median:", stats.median),
+                stat("Min:", stats.min),
+                stat("Max:", stats.max),
+            ]
+        }
+        None => vec![Spans::from(Span::styled("No active channels", Style::default().fg(app.theme.secondary())))],
+    };
+    let paragraph = Paragraph::new(lines).block(block).alignment(Alignment::Left);
+    f.render_widget(paragraph, area);
 }
 
 const CHANNEL_ITEM_SIZE: usize = 1;
 
-fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let visible_count = (area.height as usize - 2)/CHANNEL_ITEM_SIZE;
-    let vchunks_sizes: Vec<Constraint> = iter::repeat(Constraint::Length(CHANNEL_ITEM_SIZE as u16)).take(visible_count as usize).collect();
-    let vchunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints(vchunks_sizes)
-        .split(area);
+/// Minimal width of a column of the channels list, wide terminals fit several columns
+const CHANNEL_COLUMN_WIDTH: u16 = 60;
 
-    let block = Block::default().title("Active").borders(Borders::ALL);
+fn draw_chans_list<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, title: &str) {
+    let block = Block::default().title(title).borders(Borders::ALL);
     f.render_widget(block, area);
 
-    let mut chans: Vec<&ChannelStats> = app.channels_stats.iter().filter(|c| app.channels[c.info_id].state == ChannelState::Normal).collect();
-    chans.sort_by(|a, b| b.volume().partial_cmp(&a.volume()).unwrap());
-    for (i, c) in chans.iter().take(visible_count as usize).enumerate() {
-        draw_channel(f, vchunks[i], c);
-    }
-}
-
-fn draw_closing_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let visible_count = (area.height as usize - 2)/CHANNEL_ITEM_SIZE;
-    let vchunks_sizes: Vec<Constraint> = iter::repeat(Constraint::Length(CHANNEL_ITEM_SIZE as u16)).take(visible_count as usize).collect();
-    let vchunks = Layout::default()
-        .direction(Direction::Vertical)
+    let columns_count = (area.width.saturating_sub(2) / CHANNEL_COLUMN_WIDTH).max(1) as u32;
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
         .margin(1)
-        .constraints(vchunks_sizes)
+        .constraints(
+            (0..columns_count)
+                .map(|_| Constraint::Ratio(1, columns_count))
+                .collect::<Vec<_>>(),
+        )
         .split(area);
+    let chans_in_column = (area.height as usize).saturating_sub(2) / CHANNEL_ITEM_SIZE;
+    let vchunks_sizes: Vec<Constraint> = iter::repeat_n(
+        Constraint::Length(CHANNEL_ITEM_SIZE as u16),
+        chans_in_column,
+    )
+    .collect();
+    let vchunks: Vec<Vec<Rect>> = columns
+        .iter()
+        .map(|column| {
+            Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(&vchunks_sizes[..])
+                .split(*column)
+        })
+        .collect();
+    let visible_count = chans_in_column * vchunks.len();
+    let cell = |i: usize| vchunks[i / chans_in_column][i % chans_in_column];
 
-    let block = Block::default().title("Pending").borders(Borders::ALL);
-    f.render_widget(block, area);
-
-    let mut chans: Vec<&ChannelStats> = app.channels_stats.iter().filter(|c| app.channels[c.info_id].state.is_pending()).collect();
-    chans.sort_by(|a, b| b.volume().partial_cmp(&a.volume()).unwrap());
-    for (i, c) in chans.iter().take(visible_count as usize).enumerate() {
-        draw_channel(f, vchunks[i], c);
+    // Scroll the list to keep the selected channel visible
+    let selected = app.chans_selected_index();
+    let skip = (selected + 1).saturating_sub(visible_count);
+    if app.chans_grouped {
+        let peers = app.grouped_by_peer();
+        for (i, p) in peers.iter().enumerate().skip(skip).take(visible_count) {
+            draw_peer_group(f, app, cell(i - skip), p, i == selected);
+        }
+    } else {
+        let chans = app.get_chans_tab_stats();
+        for (i, c) in chans.iter().enumerate().skip(skip).take(visible_count) {
+            draw_channel(f, app, cell(i - skip), c, i == selected);
+        }
     }
 }
 
-fn draw_sleeping_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let visible_count = (area.height as usize - 2)/CHANNEL_ITEM_SIZE;
-    let vchunks_sizes: Vec<Constraint> = iter::repeat(Constraint::Length(CHANNEL_ITEM_SIZE as u16)).take(visible_count as usize).collect();
-    let vchunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints(vchunks_sizes)
+fn draw_peer_group<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, peer: &PeerStats, selected: bool) {
+    if selected {
+        f.render_widget(Block::default().style(Style::default().bg(app.theme.selected_bg())), area);
+    }
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Percentage(15),
+                Constraint::Percentage(35),
+            ]
+            .as_ref(),
+        )
         .split(area);
 
-    let block = Block::default().title("Sleeping").borders(Borders::ALL);
-    f.render_widget(block, area);
+    let alias = Paragraph::new(Spans::from(Span::styled(
+        app.shown_alias(&peer.alias, &peer.node_id),
+        Style::default().fg(app.theme.fg()),
+    )));
+    f.render_widget(alias, hchunks[0]);
 
-    let mut chans: Vec<&ChannelStats> = app.channels_stats.iter().filter(|c| app.channels[c.info_id].state.is_sleeping()).collect();
-    chans.sort_by(|a, b| b.volume().partial_cmp(&a.volume()).unwrap());
-    for (i, c) in chans.iter().take(visible_count as usize).enumerate() {
-        draw_channel(f, vchunks[i], c);
-    }
+    let count = Paragraph::new(Spans::from(Span::styled(
+        format!("x{}", peer.channels),
+        Style::default().fg(Color::Yellow),
+    )))
+    .alignment(Alignment::Right);
+    f.render_widget(count, hchunks[1]);
+
+    let volume = Paragraph::new(Spans::from(Span::styled(
+        app.format_balance(peer.volume()),
+        Style::default().fg(app.theme.secondary()),
+    )))
+    .alignment(Alignment::Right);
+    f.render_widget(volume, hchunks[2]);
 }
 
-fn draw_channel<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStats) {
-    let vchunks = Layout::default()
-        .direction(Direction::Vertical)
+fn draw_channel<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, chan: &ChannelStats, selected: bool) {
+    if selected {
+        f.render_widget(Block::default().style(Style::default().bg(app.theme.selected_bg())), area);
+    }
+
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
         .constraints(
             [
-                Constraint::Length(CHANNEL_ITEM_SIZE as u16),
+                Constraint::Percentage(45),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Percentage(25),
             ]
             .as_ref(),
         )
         .split(area);
 
-
-    let chan_tittle = vec![Spans::from(vec![Span::styled(
-        chan.alias.clone(),
-        Style::default().fg(Color::White),
-    )])];
+    let alias_color = if app.is_stuck(chan) {
+        Color::Red
+    } else if app.is_zombie(chan) {
+        Color::Magenta
+    } else if app.is_below_reserve(chan) {
+        Color::Yellow
+    } else {
+        app.theme.fg()
+    };
+    let chan_tittle = vec![Spans::from(vec![
+        super::pin_marker(app, chan),
+        Span::styled(app.shown_alias(&chan.alias, &chan.node_id), super::alias_style(app, chan, alias_color)),
+    ])];
     let paragraph = Paragraph::new(chan_tittle).alignment(Alignment::Left);
-    f.render_widget(paragraph, vchunks[0]);
-}
\ No newline at end of file
+    f.render_widget(paragraph, hchunks[0]);
+
+    let history = app.channel_volume_history(&chan.chan_id);
+    let sparkline = Sparkline::default()
+        .data(history)
+        .style(Style::default().fg(Color::Yellow));
+    f.render_widget(sparkline, hchunks[1]);
+
+    let velocity = Paragraph::new(Spans::from(Span::styled(
+        format!("x{:.2}", chan.relay_velocity()),
+        Style::default().fg(app.theme.secondary()),
+    )))
+    .alignment(Alignment::Right);
+    f.render_widget(velocity, hchunks[2]);
+
+    if let Some(feerate) = chan.feerate {
+        let color = if app.is_feerate_outlier(feerate) {
+            Color::Red
+        } else {
+            app.theme.secondary()
+        };
+        let feerate_spans = vec![Spans::from(vec![Span::styled(
+            format!("{} sat/kw", feerate.to_formatted_string(&Locale::en)),
+            Style::default().fg(color),
+        )])];
+        let paragraph = Paragraph::new(feerate_spans).alignment(Alignment::Right);
+        f.render_widget(paragraph, hchunks[3]);
+    }
+}
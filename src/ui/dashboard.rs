@@ -1,14 +1,17 @@
+use std::cmp::Ordering;
+use std::iter;
 use num_format::{Locale, ToFormattedString};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Gauge, Paragraph, Sparkline},
+    widgets::{Block, Borders, Paragraph, Sparkline},
     Frame,
 };
 
 use crate::app::{App, ChannelStats};
+use crate::format::volumes_title;
 
 pub fn draw_dashboard<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let vchunks = Layout::default()
@@ -23,23 +26,39 @@ pub fn draw_dashboard<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         )
         .split(area);
 
+    // Fiat equivalents of amounts need additional space
+    let info_width = if app.btc_price.is_some() { 56 } else { 40 };
     let toprow = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(40), Constraint::Percentage(80)].as_ref())
+        .constraints([Constraint::Length(info_width), Constraint::Percentage(80)].as_ref())
         .split(vchunks[0]);
 
-    draw_info(f, app, toprow[0]);
+    let leftcolumn = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(TOP_FEES_COUNT as u16 + 2)].as_ref())
+        .split(toprow[0]);
+
+    draw_info(f, app, leftcolumn[0]);
+    draw_top_fees(f, app, leftcolumn[1]);
     draw_active_chans(f, app, toprow[1]);
     draw_relays_amounts(f, app, vchunks[1]);
     draw_relays_volumes(f, app, vchunks[2]);
 }
 
+/// Rows of the full info panel, lower panels are drawn condensed
+const INFO_ROWS: u16 = 29;
+
 fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    if area.height < INFO_ROWS + 2 {
+        draw_info_condensed(f, app, area);
+        return;
+    }
     let hchunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(area);
 
+    let per_period = format!("per {}:", app.stats_period.label());
     let tittles = vec![
         Spans::from(vec![Span::from("Node:")]),
         Spans::from(vec![Span::from("Network:")]),
@@ -52,15 +71,24 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         Spans::from(""),
         Spans::from("Relayed"),
         Spans::from(vec![Span::from("per day:")]),
-        Spans::from(vec![Span::from("per month:")]),
+        Spans::from(vec![Span::from(per_period.clone())]),
+        Spans::from(vec![Span::from("all-time:")]),
         Spans::from(vec![Span::from("per day:")]),
-        Spans::from(vec![Span::from("per month:")]),
+        Spans::from(vec![Span::from(per_period.clone())]),
+        Spans::from(vec![Span::from("all-time:")]),
         Spans::from(vec![Span::from("percent:")]),
+        Spans::from(vec![Span::from("throughput:")]),
         Spans::from(""),
         Spans::from("Fees"),
         Spans::from(vec![Span::from("per day:")]),
-        Spans::from(vec![Span::from("per month:")]),
+        Spans::from(vec![Span::from(per_period)]),
+        Spans::from(vec![Span::from("paid month:")]),
+        Spans::from(vec![Span::from("net month:")]),
         Spans::from(vec![Span::from("ARP year:")]),
+        Spans::from(""),
+        Spans::from("Pending HTLCs"),
+        Spans::from(vec![Span::from("count:")]),
+        Spans::from(vec![Span::from("amount:")]),
     ];
     let block = Block::default()
         .title("Stats")
@@ -70,9 +98,21 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .alignment(Alignment::Left);
     f.render_widget(titles_paragraph, hchunks[0]);
 
+    let (htlcs_count, htlcs_amount) = app.pending_htlcs();
+    let htlcs_color = if htlcs_count > App::PENDING_HTLCS_WARNING {
+        Color::Red
+    } else {
+        Color::Green
+    };
+    // Figures of a period that the audit doesn't cover yet are incomplete
+    let period_color = if app.is_period_partial() {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
     let values = vec![
         Spans::from(vec![Span::styled(
-            app.node_info.alias.clone(),
+            app.shown_alias(&app.node_info.alias, &app.node_info.node_id),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::from(format!("{:?}", app.node_info.network))]),
@@ -90,31 +130,18 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
             Span::from("/"),
             Span::styled(
                 format!("{:?}", app.sleeping_chans),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(app.theme.secondary()),
+            ),
+            Span::from("/"),
+            Span::styled(
+                format!("{:?}", app.closed_chans),
+                Style::default().fg(Color::Red),
             ),
         ]),
         Spans::from(""),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.active_sats / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Green),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.pending_sats / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Yellow),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.sleeping_sats / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Gray),
-        )]),
+        balance_spans(app, app.active_sats, Color::Green),
+        balance_spans(app, app.pending_sats, Color::Yellow),
+        balance_spans(app, app.sleeping_sats, app.theme.secondary()),
         Spans::from(""),
         Spans::from(""),
         Spans::from(vec![Span::styled(
@@ -122,47 +149,35 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            app.relayed_count_month.to_formatted_string(&Locale::en),
-            Style::default().fg(Color::Green),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.relayed_day / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Green),
+            app.relayed_count_period.to_formatted_string(&Locale::en),
+            Style::default().fg(period_color),
         )]),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.relayed_month / 1000).to_formatted_string(&Locale::en)
-            ),
+            app.relays_lifetime.count.to_formatted_string(&Locale::en),
             Style::default().fg(Color::Green),
         )]),
+        sats_spans(app, app.relayed_day, Color::Green),
+        sats_spans(app, app.relayed_period, period_color),
+        sats_spans(app, app.relays_lifetime.volume, Color::Green),
         Spans::from(vec![Span::styled(
             format!("{:.2}%", app.relayed_percent()),
             Style::default().fg(Color::Green),
         )]),
+        throughput_spans(app),
+        Spans::from(""),
+        Spans::from(""),
+        sats_spans(app, app.fee_day, Color::Green),
+        sats_spans(app, app.fee_period, period_color),
+        sats_spans(app, app.fees_paid_month, Color::Yellow),
+        net_fee_spans(app),
+        Spans::from(super::return_rate_span(app, app.return_rate)),
         Spans::from(""),
         Spans::from(""),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.fee_day / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Green),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.fee_month / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Green),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!("{:.2}%", app.return_rate),
-            Style::default().fg(Color::Green),
+            htlcs_count.to_formatted_string(&Locale::en),
+            Style::default().fg(htlcs_color),
         )]),
+        sats_spans(app, htlcs_amount, htlcs_color),
     ];
     let block = Block::default().borders(Borders::TOP | Borders::BOTTOM | Borders::RIGHT);
     let values_paragraph = Paragraph::new(values)
@@ -171,7 +186,189 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     f.render_widget(values_paragraph, hchunks[1]);
 }
 
+/// Single column of the key numbers for short terminals, the most important go first
+fn draw_info_condensed<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let stat = |name: &str, value: String, color: Color| {
+        vec![
+            Span::from(format!("{}: ", name)),
+            Span::styled(value, Style::default().fg(color)),
+        ]
+    };
+    let separator = || Span::styled(" | ", Style::default().fg(app.theme.secondary()));
+    let (htlcs_count, htlcs_amount) = app.pending_htlcs();
+    let htlcs_color = if htlcs_count > App::PENDING_HTLCS_WARNING {
+        Color::Red
+    } else {
+        Color::Green
+    };
+
+    let lines = vec![
+        Spans::from(
+            [
+                vec![Span::from("ARP: "), super::return_rate_span(app, app.return_rate)],
+                vec![separator()],
+                stat("Fees/mo", app.short_amount(app.fee_month), Color::Green),
+            ]
+            .concat(),
+        ),
+        Spans::from(
+            [
+                stat("Fees/day", app.short_amount(app.fee_day), Color::Green),
+                vec![separator()],
+                stat("Paid/mo", app.short_amount(app.fees_paid_month), Color::Yellow),
+            ]
+            .concat(),
+        ),
+        Spans::from(
+            [
+                stat("Relayed/mo", app.short_amount(app.relayed_month), Color::Green),
+                vec![separator()],
+                stat("count", app.short_count(app.relayed_count_month), Color::Green),
+            ]
+            .concat(),
+        ),
+        Spans::from(
+            [
+                stat("Relayed/day", app.short_amount(app.relayed_day), Color::Green),
+                vec![separator()],
+                stat("count", app.short_count(app.relayed_count_day), Color::Green),
+            ]
+            .concat(),
+        ),
+        Spans::from(vec![
+            Span::from("Channels: "),
+            Span::styled(format!("{}", app.active_chans), Style::default().fg(Color::Green)),
+            Span::from("/"),
+            Span::styled(format!("{}", app.pending_chans), Style::default().fg(Color::Yellow)),
+            Span::from("/"),
+            Span::styled(format!("{}", app.sleeping_chans), Style::default().fg(app.theme.secondary())),
+            Span::from("/"),
+            Span::styled(format!("{}", app.closed_chans), Style::default().fg(Color::Red)),
+        ]),
+        Spans::from(stat("Active", app.short_balance(app.active_sats), Color::Green)),
+        Spans::from(
+            [
+                stat("HTLCs", htlcs_count.to_string(), htlcs_color),
+                vec![separator()],
+                stat("amount", app.short_amount(htlcs_amount), htlcs_color),
+            ]
+            .concat(),
+        ),
+        Spans::from(stat(
+            "Node",
+            format!(
+                "{} ({:?})",
+                app.shown_alias(&app.node_info.alias, &app.node_info.node_id),
+                app.node_info.network
+            ),
+            Color::Green,
+        )),
+    ];
+    let block = Block::default().title("Stats").borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block).alignment(Alignment::Left);
+    f.render_widget(paragraph, area);
+}
+
+/// Amount in the chosen unit with its fiat equivalent if we know the BTC price
+fn sats_spans(app: &App, msat: u64, color: Color) -> Spans<'static> {
+    let mut spans = vec![Span::styled(
+        app.amount_unit.format(msat),
+        Style::default().fg(color),
+    )];
+    if let Some(fiat) = app.to_fiat(msat) {
+        spans.push(Span::styled(
+            format!(" {:.*} {}", app.fiat_decimals, fiat, app.price_currency),
+            Style::default().fg(app.theme.secondary()),
+        ));
+    }
+    Spans::from(spans)
+}
+
+/// Month fees earned minus fees paid, red when the node spends more than it earns
+fn net_fee_spans(app: &App) -> Spans<'static> {
+    let net = app.net_fee_month();
+    let (sign, color) = if net < 0 { ("-", Color::Red) } else { ("", Color::Green) };
+    Spans::from(Span::styled(
+        format!("{}{}", sign, app.amount_unit.format(net.unsigned_abs())),
+        Style::default().fg(color),
+    ))
+}
+
+/// Balance like `sats_spans`, the fiat equivalent is left out in the anonymized mode as it
+/// would reveal the balance
+fn balance_spans(app: &App, msat: u64, color: Color) -> Spans<'static> {
+    if app.anonymize {
+        Spans::from(Span::styled(app.format_balance(msat), Style::default().fg(color)))
+    } else {
+        sats_spans(app, msat, color)
+    }
+}
+
+/// Volume relayed over the last hour with the trend against the hour before
+fn throughput_spans(app: &App) -> Spans<'static> {
+    let (current, previous) = app.recent_throughput();
+    let trend = match current.cmp(&previous) {
+        Ordering::Greater => Span::styled(" ▲", Style::default().fg(Color::Green)),
+        Ordering::Less => Span::styled(" ▼", Style::default().fg(Color::Red)),
+        Ordering::Equal => Span::styled(" =", Style::default().fg(app.theme.secondary())),
+    };
+    Spans::from(vec![
+        Span::styled(
+            format!("{} sats/h", (current / 1000).to_formatted_string(&Locale::en)),
+            Style::default().fg(Color::Green),
+        ),
+        trend,
+    ])
+}
+
+/// How many of the most profitable channels we show
+const TOP_FEES_COUNT: usize = 5;
+
+fn draw_top_fees<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(area);
+
+    let top = app.get_top_fee_channels(TOP_FEES_COUNT);
+    let aliases: Vec<Spans> = top
+        .iter()
+        .map(|c| Spans::from(Span::from(app.shown_alias(&c.alias, &c.node_id))))
+        .collect();
+    let fees: Vec<Spans> = top
+        .iter()
+        .map(|c| {
+            Spans::from(Span::styled(
+                format!("{} sats", (c.relays_fees / 1000).to_formatted_string(&Locale::en)),
+                Style::default().fg(Color::Green),
+            ))
+        })
+        .collect();
+
+    let block = Block::default().title("Top fees").borders(Borders::ALL);
+    f.render_widget(block, area);
+    f.render_widget(Paragraph::new(aliases).alignment(Alignment::Left), hchunks[0]);
+    f.render_widget(Paragraph::new(fees).alignment(Alignment::Right), hchunks[1]);
+}
+
+/// Channels with the most relayed volume go first
+fn sorted_by_relays(app: &App) -> Vec<ChannelStats> {
+    let mut chans = app.channels_stats.clone();
+    chans.sort_by(|a, b| 
+        if a.relays_volume == b.relays_volume {
+            a.chan_id.partial_cmp(&b.chan_id).unwrap()
+        } else {
+            b.relays_volume.partial_cmp(&a.relays_volume).unwrap()
+        });
+    chans
+}
+
 fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    if app.dashboard_compact {
+        draw_compact_chans(f, app, area);
+        return;
+    }
     let headbody = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
@@ -202,28 +399,98 @@ fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         })
         .collect();
 
-    let chans_in_column = 4;
+    let chans_in_column = App::CHANNELS_PAGE_SIZE / 2;
     let chans_to_draw = chans_in_column * vchunks.len();
     let chans_to_skip = app.channels_page as usize * chans_to_draw;
-    let mut chans = app.channels_stats.clone();
-    chans.sort_by(|a, b| 
-        if a.relays_volume == b.relays_volume {
-            a.chan_id.partial_cmp(&b.chan_id).unwrap()
-        } else {
-            b.relays_volume.partial_cmp(&a.relays_volume).unwrap()
-        });
+    let chans = sorted_by_relays(app);
+    // Filter before paging, otherwise pages with hosted channels have holes
     for (i, c) in chans
         .iter()
+        .filter(|c| c.is_normal_channel())
         .skip(chans_to_skip)
         .take(chans_to_draw)
-        .filter(|c| c.is_normal_channel())
         .enumerate()
     {
-        draw_active_chan(f, vchunks[i / chans_in_column][i % chans_in_column], c);
+        draw_active_chan(f, app, vchunks[i / chans_in_column][i % chans_in_column], c);
     }
 }
 
-fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStats) {
+fn draw_compact_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    // The bar has no labels, so the title tells which balance it fills
+    let filled_side = if app.gauge_remote { "remote" } else { "local" };
+    let block = Block::default()
+        .title(format!(
+            "Channels, bars show {} balance (m - cards view, b - flip)",
+            filled_side
+        ))
+        .borders(Borders::ALL);
+    f.render_widget(block, area);
+
+    let rows = area.height.saturating_sub(2) as usize;
+    // Paging keys count pages with the size of the list that is actually drawn
+    app.compact_rows.set(rows);
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(iter::repeat_n(Constraint::Length(1), rows).collect::<Vec<_>>())
+        .split(area);
+
+    let chans: Vec<ChannelStats> = sorted_by_relays(app)
+        .into_iter()
+        .filter(|c| c.is_normal_channel())
+        .collect();
+    let pages = chans.len().div_ceil(rows.max(1)).max(1);
+    let page = (app.channels_page as usize).min(pages - 1);
+    for (i, c) in chans.iter().skip(page * rows).take(rows).enumerate() {
+        draw_compact_chan(f, app, vchunks[i], c);
+    }
+}
+
+/// Single line with alias, balance bar, relays count and fees
+fn draw_compact_chan<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, chan: &ChannelStats) {
+    let hchunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+                Constraint::Percentage(15),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let alias = Paragraph::new(Spans::from(Span::styled(
+        app.shown_alias(&chan.alias, &chan.node_id),
+        super::alias_style(app, chan, app.theme.fg()),
+    )));
+    f.render_widget(alias, hchunks[0]);
+
+    let width = hchunks[1].width.saturating_sub(1) as usize;
+    let filled = (chan.channel_ratio(app.gauge_remote) * width as f64).round() as usize;
+    let bar = Paragraph::new(Spans::from(vec![
+        Span::styled("█".repeat(filled), Style::default().fg(Color::Blue)),
+        Span::styled("░".repeat(width - filled), Style::default().fg(app.theme.secondary())),
+    ]));
+    f.render_widget(bar, hchunks[1]);
+
+    let relays = Paragraph::new(Spans::from(Span::styled(
+        app.short_count(chan.relays_amount),
+        Style::default().fg(app.theme.secondary()),
+    )))
+    .alignment(Alignment::Right);
+    f.render_widget(relays, hchunks[2]);
+
+    let fees = Paragraph::new(Spans::from(Span::styled(
+        app.short_amount(chan.relays_fees),
+        Style::default().fg(Color::Green),
+    )))
+    .alignment(Alignment::Right);
+    f.render_widget(fees, hchunks[3]);
+}
+
+fn draw_active_chan<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, chan: &ChannelStats) {
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -242,44 +509,26 @@ fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStat
         .split(vchunks[2]);
 
     let chan_tittle = vec![Spans::from(vec![Span::styled(
-        chan.alias.clone(),
-        Style::default().fg(Color::White),
+        app.shown_alias(&chan.alias, &chan.node_id),
+        super::alias_style(app, chan, app.theme.fg()),
     )])];
     let paragraph = Paragraph::new(chan_tittle).alignment(Alignment::Left);
     f.render_widget(paragraph, vchunks[0]);
 
-    let capacity = chan.local + chan.remote;
-
-    let channel_ratio = if capacity == 0 {
-        0.0
-    } else {
-        chan.local as f64 / capacity as f64
-    };
-    let local = (chan.local / 1000).to_formatted_string(&Locale::en);
-    let remote = (chan.remote / 1000).to_formatted_string(&Locale::en);
-    let gauge = Gauge::default()
-        .gauge_style(
-            Style::default()
-                .fg(Color::Blue)
-                .bg(Color::Gray)
-                .add_modifier(Modifier::ITALIC),
-        )
-        .ratio(channel_ratio)
-        .label(format!("{}/{}", local, remote));
-    f.render_widget(gauge, vchunks[1]);
+    f.render_widget(super::balance_gauge(app, chan), vchunks[1]);
 
     let col0_spans = vec![
         Spans::from(vec![
             Span::from("Relays: ".to_owned()),
             Span::styled(
                 format!("{}", chan.relays_amount),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(app.theme.secondary()),
             ),
         ]),
         Spans::from(vec![
             Span::from("Fees: ".to_owned()),
             Span::styled(
-                (chan.relays_fees / 1000).to_formatted_string(&Locale::en),
+                app.short_amount(chan.relays_fees),
                 Style::default().fg(Color::Green),
             ),
         ]),
@@ -291,8 +540,8 @@ fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStat
         Spans::from(vec![
             Span::from("Volume: ".to_owned()),
             Span::styled(
-                (chan.relays_volume / 1000).to_formatted_string(&Locale::en),
-                Style::default().fg(Color::Gray),
+                app.short_amount(chan.relays_volume),
+                Style::default().fg(app.theme.secondary()),
             ),
         ]),
         Spans::from(vec![
@@ -326,9 +575,10 @@ fn draw_relays_volumes<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let sparkline = Sparkline::default()
         .block(
             Block::default()
-                .title(format!(
-                    "24h relay volumes, (max: {} sats)",
-                    (app.relays_maximum_volume / 1000).to_formatted_string(&Locale::en)
+                .title(volumes_title(
+                    "relay volumes",
+                    app.volumes_log_scale,
+                    app.relays_maximum_volume,
                 ))
                 .borders(Borders::LEFT | Borders::RIGHT),
         )
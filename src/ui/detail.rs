@@ -0,0 +1,237 @@
+use num_format::{Locale, ToFormattedString};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, ChannelStats};
+
+/// How many of the latest relays through the channel we list
+const RECENT_RELAYS: usize = 8;
+
+pub fn draw_detail<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let chan = match app.get_selected_channel() {
+        Some(chan) => chan,
+        None => return,
+    };
+    let info = &app.channels[chan.info_id];
+
+    let mut lines = vec![
+        field("Alias", app.shown_alias(&chan.alias, &chan.node_id)),
+        field("Node id", app.shown_id(&chan.node_id)),
+        field("Channel id", app.shown_id(&chan.chan_id)),
+        field(
+            "Short id",
+            info.data
+                .as_ref()
+                .and_then(|d| d.short_channel_id.as_deref())
+                .map_or_else(|| "-".to_owned(), |id| app.shown_id(id)),
+        ),
+        state_field(app, chan),
+        field("Visibility", if chan.public { "Public" } else { "Private" }.to_owned()),
+        Spans::from(""),
+        field("Local", app.format_balance(chan.local)),
+        field("Remote", app.format_balance(chan.remote)),
+        reserve_field(app, chan),
+        feerate_field(app, chan),
+        fee_policy_field(app, chan),
+        Spans::from(""),
+        field("Relays", chan.relays_amount.to_formatted_string(&Locale::en)),
+        field("Volume", sats(chan.relays_volume)),
+        field("Fees", sats(chan.relays_fees)),
+        fee_trend_field(app, chan),
+        Spans::from(""),
+        Spans::from("Recent relays"),
+    ];
+    let relays = app.channel_relays(&chan.chan_id, RECENT_RELAYS);
+    if relays.is_empty() {
+        lines.push(Spans::from(Span::styled("No relays", Style::default().fg(app.theme.secondary()))));
+    }
+    for relay in relays {
+        let time = app.timezone.format(relay.timestamp.unix as i64, "%Y-%m-%d %H:%M");
+        let direction = if relay.from_channel_id == chan.chan_id {
+            "in "
+        } else {
+            "out"
+        };
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("{} ", time),
+                Style::default().fg(app.theme.secondary()),
+            ),
+            Span::from(format!("{} ", direction)),
+            Span::styled(sats(relay.amount_in), Style::default().fg(Color::Green)),
+            Span::from(format!(
+                ", fee {} msat",
+                relay.amount_in.saturating_sub(relay.amount_out).to_formatted_string(&Locale::en)
+            )),
+        ]));
+    }
+    let proposals = app.channel_proposals(&chan.chan_id);
+    if !proposals.is_empty() {
+        lines.push(Spans::from(""));
+        lines.push(Spans::from("Pending proposals"));
+        for proposal in proposals {
+            lines.push(Spans::from(Span::styled(
+                proposal.to_string(),
+                Style::default().fg(Color::Magenta),
+            )));
+        }
+    }
+    lines.push(Spans::from(""));
+    if app.commands_enabled {
+        lines.push(Spans::from(Span::styled(
+            "x - close channel, X - force close channel",
+            Style::default().fg(Color::Yellow),
+        )));
+    } else {
+        lines.push(Spans::from(Span::styled(
+            "Commands are disabled, see --enable-commands",
+            Style::default().fg(app.theme.secondary()),
+        )));
+    }
+
+    let block = Block::default()
+        .title("Channel details")
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(app.theme.style())
+        .alignment(Alignment::Left);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn field(name: &str, value: String) -> Spans<'static> {
+    Spans::from(vec![
+        Span::from(format!("{}: ", name)),
+        Span::styled(value, Style::default().fg(Color::Green)),
+    ])
+}
+
+/// Daily fees of the channel as a one line sparkline, oldest first
+fn fee_trend_field(app: &App, chan: &ChannelStats) -> Spans<'static> {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let history = app.channel_fee_history(&chan.chan_id);
+    let (min, max) = match (history.iter().min(), history.iter().max()) {
+        (Some(min), Some(max)) if history.len() > 1 => (*min, *max),
+        _ => {
+            return Spans::from(vec![
+                Span::from(format!("Fee trend ({}d): ", App::CHANNEL_FEE_DAYS)),
+                Span::styled("not enough history", Style::default().fg(app.theme.secondary())),
+            ])
+        }
+    };
+    let range = (max - min).max(1) as u128;
+    let trend: String = history
+        .iter()
+        .map(|fees| BARS[((fees - min) as u128 * (BARS.len() - 1) as u128 / range) as usize])
+        .collect();
+    let color = match history.last().cmp(&history.first()) {
+        std::cmp::Ordering::Greater => Color::Green,
+        std::cmp::Ordering::Less => Color::Red,
+        std::cmp::Ordering::Equal => app.theme.secondary(),
+    };
+    Spans::from(vec![
+        Span::from(format!("Fee trend ({}d): ", App::CHANNEL_FEE_DAYS)),
+        Span::styled(trend, Style::default().fg(color)),
+    ])
+}
+
+fn sats(msat: u64) -> String {
+    format!("{} sats", (msat / 1000).to_formatted_string(&Locale::en))
+}
+
+fn state_field(app: &App, chan: &ChannelStats) -> Spans<'static> {
+    if let Some(duration) = app.stuck_duration(chan).filter(|_| app.is_stuck(chan)) {
+        return Spans::from(vec![
+            Span::from("State: "),
+            Span::styled(
+                format!(
+                    "{:?} for {} hours, stuck, try to reconnect the peer",
+                    chan.chan_state,
+                    duration / 3600
+                ),
+                Style::default().fg(Color::Red),
+            ),
+        ]);
+    }
+    match app.offline_duration(&chan.chan_id) {
+        Some(duration) => {
+            let color = if app.is_zombie(chan) {
+                Color::Magenta
+            } else {
+                app.theme.secondary()
+            };
+            Spans::from(vec![
+                Span::from("State: "),
+                Span::styled(
+                    format!("{:?} for {} days", chan.chan_state, duration / (24 * 3600)),
+                    Style::default().fg(color),
+                ),
+            ])
+        }
+        None => field("State", format!("{:?}", chan.chan_state)),
+    }
+}
+
+fn reserve_field(app: &App, chan: &ChannelStats) -> Spans<'static> {
+    let info = &app.channels[chan.info_id];
+    match (info.spendable(), info.reserve()) {
+        (Some(spendable), Some(reserve)) => {
+            let color = if app.is_below_reserve(chan) {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            Spans::from(vec![
+                Span::from("Spendable: "),
+                Span::styled(sats(spendable), Style::default().fg(color)),
+                Span::from(format!(" (reserve {})", sats(reserve))),
+            ])
+        }
+        _ => field("Spendable", "-".to_owned()),
+    }
+}
+
+fn fee_policy_field(app: &App, chan: &ChannelStats) -> Spans<'static> {
+    let (base, ppm) = match app.fee_policy(chan) {
+        Some(policy) => policy,
+        None => return field("Fee policy", "-".to_owned()),
+    };
+    let policy = format!("{} msat + {} ppm", base.to_formatted_string(&Locale::en), ppm);
+    match app.fee_policy_flag(chan) {
+        Some(flag) => Spans::from(vec![
+            Span::from("Fee policy: "),
+            Span::styled(
+                format!("{} ({})", policy, flag),
+                Style::default().fg(Color::Red),
+            ),
+        ]),
+        None => field("Fee policy", policy),
+    }
+}
+
+fn feerate_field(app: &App, chan: &ChannelStats) -> Spans<'static> {
+    match chan.feerate {
+        Some(feerate) => {
+            let color = if app.is_feerate_outlier(feerate) {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            Spans::from(vec![
+                Span::from("Feerate: "),
+                Span::styled(
+                    format!("{} sat/kw", feerate.to_formatted_string(&Locale::en)),
+                    Style::default().fg(color),
+                ),
+            ])
+        }
+        None => field("Feerate", "-".to_owned()),
+    }
+}
@@ -0,0 +1,37 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::App;
+
+pub fn draw_errors<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let lines: Vec<Spans> = app
+        .error_log
+        .iter()
+        .rev()
+        .skip(app.error_log_scroll)
+        .map(|e| {
+            let time = app.timezone.format(e.timestamp, "%Y-%m-%d %H:%M:%S");
+            Spans::from(vec![
+                Span::styled(
+                    format!("{} ", time),
+                    Style::default().fg(app.theme.secondary()),
+                ),
+                Span::styled(app.shown_text(&e.message), Style::default().fg(Color::Red)),
+            ])
+        })
+        .collect();
+    let block = Block::default()
+        .title(format!("Errors ({})", app.error_log.len()))
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, area);
+}
@@ -1,23 +1,38 @@
-use num_format::{Locale, ToFormattedString};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Gauge, Paragraph, Sparkline},
+    widgets::{Block, Borders, Paragraph, Sparkline},
     Frame,
 };
 
 use crate::app::{App, ChannelStats};
+use crate::format::volumes_title;
+use crate::valuation::FiatValuation;
 
 pub fn draw_fiat<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(80),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
     let toprow = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Length(40), Constraint::Percentage(80)].as_ref())
-        .split(area);
+        .split(vchunks[0]);
 
     draw_info(f, app, toprow[0]);
     draw_active_chans(f, app, toprow[1]);
+    draw_relays_amounts(f, app, vchunks[1]);
+    draw_relays_volumes(f, app, vchunks[2]);
 }
 
 fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
@@ -26,16 +41,25 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(area);
 
-    let tittles = vec![
-        Spans::from("Channels activity"),
-        Spans::from("Channels volume"),
-        Spans::from(vec![Span::from("Active:")]),
-        Spans::from(vec![Span::from("Suspended:")]),
-        Spans::from(vec![Span::from("Offline:")]),
+    // Balances in different currencies don't add up, each currency has own rows
+    let currencies = app.fiat_currencies();
+    let mut tittles = vec![Spans::from("Channels activity")];
+    for currency in currencies.iter() {
+        tittles.extend([
+            Spans::from(format!("Volume, {}", currency)),
+            Spans::from(vec![Span::from("Active:")]),
+            Spans::from(vec![Span::from("Suspended:")]),
+            Spans::from(vec![Span::from("Offline:")]),
+            Spans::from(vec![Span::from("Fiat balance:")]),
+        ]);
+    }
+    tittles.extend([
         Spans::from(""),
-        Spans::from(vec![Span::from("Fiat balance:")]),
         Spans::from(vec![Span::from("Hedged:")]),
-    ];
+        Spans::from(vec![Span::from("Unhedged:")]),
+        Spans::from(vec![Span::from("Hedge ratio:")]),
+        Spans::from(vec![Span::from("Margin calls:")]),
+    ]);
     let block = Block::default()
         .title("Stats")
         .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT);
@@ -44,55 +68,54 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .alignment(Alignment::Left);
     f.render_widget(titles_paragraph, hchunks[0]);
 
-    let values = vec![
-        Spans::from(vec![
-            Span::styled(
-                format!("{:?}", app.get_active_fiat_chans()),
-                Style::default().fg(Color::Green),
-            ),
-            Span::from("/"),
-            Span::styled(
-                format!("{:?}", app.get_suspended_fiat_chans()),
-                Style::default().fg(Color::Yellow),
-            ),
-            Span::from("/"),
-            Span::styled(
-                format!("{:?}", app.get_offline_fiat_chans()),
-                Style::default().fg(Color::Gray),
-            ),
-        ]),
-        Spans::from(""),
+    let exposure = app.fiat_exposure();
+    let balance = |currency: &str, value: f64| {
         Spans::from(vec![Span::styled(
-            format!(
-                "{:.2} €",
-                app.get_fiat_balance_by(|state| state.is_normal())
-            ),
+            app.format_fiat_balance(value, currency),
             Style::default().fg(Color::Green),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{:.2} €",
-                app.get_fiat_balance_by(|state| state.is_pending())
-            ),
+        )])
+    };
+    let mut values = vec![Spans::from(vec![
+        Span::styled(
+            format!("{:?}", app.get_active_fiat_chans()),
             Style::default().fg(Color::Green),
-        )]),
+        ),
+        Span::from("/"),
+        Span::styled(
+            format!("{:?}", app.get_suspended_fiat_chans()),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::from("/"),
+        Span::styled(
+            format!("{:?}", app.get_offline_fiat_chans()),
+            Style::default().fg(app.theme.secondary()),
+        ),
+    ])];
+    for currency in currencies.iter() {
+        values.extend([
+            Spans::from(""),
+            balance(currency, app.get_fiat_balance_by(currency, |state| state.is_normal())),
+            balance(currency, app.get_fiat_balance_by(currency, |state| state.is_pending())),
+            balance(currency, app.get_fiat_balance_by(currency, |state| state.is_sleeping())),
+            balance(currency, app.get_total_fiat_balance(currency)),
+        ]);
+    }
+    values.extend([
+        Spans::from(""),
         Spans::from(vec![Span::styled(
-            format!(
-                "{:.2} €",
-                app.get_fiat_balance_by(|state| state.is_sleeping())
-            ),
+            app.format_balance(exposure.hedged),
             Style::default().fg(Color::Green),
         )]),
-        Spans::from(""),
         Spans::from(vec![Span::styled(
-            format!("{:.2} €", app.get_total_fiat_balance()),
-            Style::default().fg(Color::Green),
+            app.format_balance(exposure.unhedged),
+            Style::default().fg(Color::Yellow),
         )]),
         Spans::from(vec![Span::styled(
-            format!("{:.2} €", 0.0),
+            format!("{:.2}%", 100.0 * exposure.hedge_ratio),
             Style::default().fg(Color::Green),
         )]),
-    ];
+        margin_calls_spans(app),
+    ]);
     let block = Block::default().borders(Borders::TOP | Borders::BOTTOM | Borders::RIGHT);
     let values_paragraph = Paragraph::new(values)
         .block(block)
@@ -100,6 +123,12 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     f.render_widget(values_paragraph, hchunks[1]);
 }
 
+fn margin_calls_spans(app: &App) -> Spans<'static> {
+    let count = app.pending_margin_proposals().len();
+    let color = if count > 0 { Color::Magenta } else { Color::Green };
+    Spans::from(Span::styled(format!("{}", count), Style::default().fg(color)))
+}
+
 fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let headbody = Layout::default()
         .direction(Direction::Vertical)
@@ -112,7 +141,7 @@ fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(headbody[1]);
 
-    let chans_in_column: usize = 6;
+    let chans_in_column: usize = App::FIAT_PAGE_SIZE / 2;
     let vchunk_constraints: Vec<Constraint> = (0..chans_in_column)
         .map(|_| Constraint::Percentage(100 / chans_in_column as u16))
         .collect();
@@ -129,19 +158,27 @@ fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
 
     let chans_to_draw = chans_in_column * vchunks.len();
     let chans_to_skip = app.channels_page as usize * chans_to_draw;
-    let mut chans = app.fiat_stats.clone();
-    chans.sort_by(|a, b| b.fiat_balance().partial_cmp(&a.fiat_balance()).unwrap());
+    let hidden = app.fiat_stats.iter().filter(|c| app.is_hidden(c)).count();
+    if hidden > 0 {
+        let header = Paragraph::new(Spans::from(Span::styled(
+            format!("{} idle channels hidden", hidden),
+            Style::default().fg(app.theme.secondary()),
+        )))
+        .alignment(Alignment::Right);
+        f.render_widget(header, headbody[0]);
+    }
+    let chans = app.get_fiat_tab_stats();
     for (i, c) in chans
         .iter()
         .skip(chans_to_skip)
         .take(chans_to_draw)
         .enumerate()
     {
-        draw_active_chan(f, vchunks[i / chans_in_column][i % chans_in_column], c);
+        draw_active_chan(f, app, vchunks[i / chans_in_column][i % chans_in_column], c);
     }
 }
 
-fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStats) {
+fn draw_active_chan<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, chan: &ChannelStats) {
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -159,45 +196,48 @@ fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStat
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(vchunks[2]);
 
-    let chan_tittle = vec![Spans::from(vec![Span::styled(
-        chan.alias.clone(),
-        Style::default().fg(Color::White),
-    )])];
+    // Pending margin calls need the operator attention
+    let alias_color = if app.margin_proposal(&chan.chan_id).is_some() {
+        Color::Magenta
+    } else {
+        app.theme.fg()
+    };
+    let chan_tittle = vec![Spans::from(vec![
+        super::pin_marker(app, chan),
+        Span::styled(
+            app.shown_alias(&chan.alias, &chan.node_id),
+            super::alias_style(app, chan, alias_color),
+        ),
+    ])];
     let paragraph = Paragraph::new(chan_tittle).alignment(Alignment::Left);
     f.render_widget(paragraph, vchunks[0]);
 
-    let capacity = chan.local + chan.remote;
-
-    let channel_ratio = if capacity == 0 {
-        0.0
-    } else {
-        chan.local as f64 / capacity as f64
-    };
-    let local = (chan.local / 1000).to_formatted_string(&Locale::en);
-    let remote = (chan.remote / 1000).to_formatted_string(&Locale::en);
-    let gauge = Gauge::default()
-        .gauge_style(
-            Style::default()
-                .fg(Color::Blue)
-                .bg(Color::Gray)
-                .add_modifier(Modifier::ITALIC),
-        )
-        .ratio(channel_ratio)
-        .label(format!("{}/{}", local, remote));
-    f.render_widget(gauge, vchunks[1]);
+    f.render_widget(super::balance_gauge(app, chan), vchunks[1]);
 
+    let mut rate_spans = vec![
+        Span::from("Rate: ".to_owned()),
+        Span::styled(
+            format!("{} sats", chan.rate() / 1000),
+            Style::default().fg(app.theme.secondary()),
+        ),
+    ];
+    match app.fiat_rate_delta(&chan.chan_id) {
+        Some(delta) if delta > 0 => rate_spans.push(Span::styled(
+            format!(" ▲{}", delta / 1000),
+            Style::default().fg(Color::Green),
+        )),
+        Some(delta) if delta < 0 => rate_spans.push(Span::styled(
+            format!(" ▼{}", -delta / 1000),
+            Style::default().fg(Color::Red),
+        )),
+        _ => (),
+    }
     let col0_spans = vec![
-        Spans::from(vec![
-            Span::from("Rate: ".to_owned()),
-            Span::styled(
-                format!("{} sats", chan.rate() / 1000),
-                Style::default().fg(Color::Gray),
-            ),
-        ]),
+        Spans::from(rate_spans),
         Spans::from(vec![
             Span::from("Balance: ".to_owned()),
             Span::styled(
-                format!("{:.2} €", chan.fiat_balance()),
+                app.format_fiat_balance(chan.fiat_balance(), chan.currency()),
                 Style::default().fg(Color::Green),
             ),
         ]),
@@ -205,13 +245,33 @@ fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStat
     let stats_col0 = Paragraph::new(col0_spans).alignment(Alignment::Left);
     f.render_widget(stats_col0, hchunks[0]);
 
-    let col1_spans = vec![Spans::from(vec![
+    let mut col1_spans = vec![Spans::from(vec![
         Span::from("R.Rate: ".to_owned()),
         Span::styled(
-            format!("{} €", chan.reverse_rate().round() as u64),
-            Style::default().fg(Color::Gray),
+            app.format_fiat(chan.reverse_rate(), chan.currency()),
+            Style::default().fg(app.theme.secondary()),
         ),
     ])];
+    // The other valuation of the balance, next to the one chosen with --fiat-valuation
+    let other_balance = match app.fiat_valuation {
+        FiatValuation::Channel => chan.market_balance().map(|b| ("Market: ", b)),
+        FiatValuation::Oracle => Some(("Intrinsic: ", chan.intrinsic_balance())),
+    };
+    if let Some((label, balance)) = other_balance {
+        col1_spans.push(Spans::from(vec![
+            Span::from(label.to_owned()),
+            Span::styled(
+                app.format_fiat_balance(balance, chan.currency()),
+                Style::default().fg(app.theme.secondary()),
+            ),
+        ]));
+    }
+    for proposal in app.channel_proposals(&chan.chan_id) {
+        col1_spans.push(Spans::from(Span::styled(
+            proposal.to_string(),
+            Style::default().fg(Color::Magenta),
+        )));
+    }
     let stats_col1 = Paragraph::new(col1_spans).alignment(Alignment::Left);
     f.render_widget(stats_col1, hchunks[1]);
 }
@@ -221,12 +281,12 @@ fn draw_relays_amounts<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .block(
             Block::default()
                 .title(format!(
-                    "24h relay count (max: {})",
-                    app.relays_maximum_count
+                    "24h fiat relay count (max: {})",
+                    app.fiat_relays_maximum_count
                 ))
                 .borders(Borders::LEFT | Borders::RIGHT),
         )
-        .data(&app.relays_amounts_line)
+        .data(&app.fiat_relays_amounts_line)
         .style(Style::default().fg(Color::Red));
     f.render_widget(sparkline, area);
 }
@@ -235,13 +295,14 @@ fn draw_relays_volumes<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let sparkline = Sparkline::default()
         .block(
             Block::default()
-                .title(format!(
-                    "24h relay volumes, (max: {} sats)",
-                    (app.relays_maximum_volume / 1000).to_formatted_string(&Locale::en)
+                .title(volumes_title(
+                    "fiat relay volumes",
+                    app.volumes_log_scale,
+                    app.fiat_relays_maximum_volume,
                 ))
                 .borders(Borders::LEFT | Borders::RIGHT),
         )
-        .data(&app.relays_volumes_line)
+        .data(&app.fiat_relays_volumes_line)
         .style(Style::default().fg(Color::Yellow));
     f.render_widget(sparkline, area);
 }
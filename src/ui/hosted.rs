@@ -2,13 +2,14 @@ use num_format::{Locale, ToFormattedString};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Gauge, Paragraph, Sparkline},
+    widgets::{Block, Borders, Paragraph, Sparkline},
     Frame,
 };
 
 use crate::app::{App, ChannelStats};
+use crate::format::volumes_title;
 
 pub fn draw_hosted<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let vchunks = Layout::default()
@@ -67,6 +68,8 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .alignment(Alignment::Left);
     f.render_widget(titles_paragraph, hchunks[0]);
 
+    let totals = &app.hosted_totals;
+    let return_rate = app.get_hosted_return_rate();
     let values = vec![
         Spans::from(vec![
             Span::styled(
@@ -81,79 +84,55 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
             Span::from("/"),
             Span::styled(
                 format!("{:?}", app.sleeping_chans),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(app.theme.secondary()),
             ),
         ]),
         Spans::from(""),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.active_sats / 1000).to_formatted_string(&Locale::en)
-            ),
+            app.format_balance(app.active_sats),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.pending_sats / 1000).to_formatted_string(&Locale::en)
-            ),
+            app.format_balance(app.pending_sats),
             Style::default().fg(Color::Yellow),
         )]),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.sleeping_sats / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Gray),
+            app.format_balance(app.sleeping_sats),
+            Style::default().fg(app.theme.secondary()),
         )]),
         Spans::from(""),
         Spans::from(""),
         Spans::from(vec![Span::styled(
-            app.relayed_count_day.to_formatted_string(&Locale::en),
+            totals.count_day.to_formatted_string(&Locale::en),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            app.relayed_count_month.to_formatted_string(&Locale::en),
+            totals.count_month.to_formatted_string(&Locale::en),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.relayed_day / 1000).to_formatted_string(&Locale::en)
-            ),
+            app.amount_unit.format(totals.relayed_day),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.relayed_month / 1000).to_formatted_string(&Locale::en)
-            ),
+            app.amount_unit.format(totals.relayed_month),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            format!("{:.2}%", app.relayed_percent()),
+            format!("{:.2}%", app.hosted_relayed_percent()),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(""),
         Spans::from(""),
         Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.fee_day / 1000).to_formatted_string(&Locale::en)
-            ),
-            Style::default().fg(Color::Green),
-        )]),
-        Spans::from(vec![Span::styled(
-            format!(
-                "{} sats",
-                (app.fee_month / 1000).to_formatted_string(&Locale::en)
-            ),
+            app.amount_unit.format(totals.fee_day),
             Style::default().fg(Color::Green),
         )]),
         Spans::from(vec![Span::styled(
-            format!("{:.2}%", app.return_rate),
+            app.amount_unit.format(totals.fee_month),
             Style::default().fg(Color::Green),
         )]),
+        Spans::from(super::return_rate_span(app, return_rate)),
     ];
     let block = Block::default().borders(Borders::TOP | Borders::BOTTOM | Borders::RIGHT);
     let values_paragraph = Paragraph::new(values)
@@ -193,22 +172,30 @@ fn draw_active_chans<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         })
         .collect();
 
-    let chans_in_column = 4;
+    let chans_in_column = App::CHANNELS_PAGE_SIZE / 2;
     let chans_to_draw = chans_in_column * vchunks.len();
     let chans_to_skip = app.channels_page as usize * chans_to_draw;
-    let mut chans = app.hosted_stats.clone();
-    chans.sort_by(|a, b| b.relays_volume.partial_cmp(&a.relays_volume).unwrap());
+    let hidden = app.hosted_stats.iter().filter(|c| app.is_hidden(c)).count();
+    if hidden > 0 {
+        let header = Paragraph::new(Spans::from(Span::styled(
+            format!("{} idle channels hidden", hidden),
+            Style::default().fg(app.theme.secondary()),
+        )))
+        .alignment(Alignment::Right);
+        f.render_widget(header, headbody[0]);
+    }
+    let chans = app.get_hosted_tab_stats();
     for (i, c) in chans
         .iter()
         .skip(chans_to_skip)
         .take(chans_to_draw)
         .enumerate()
     {
-        draw_active_chan(f, vchunks[i / chans_in_column][i % chans_in_column], c);
+        draw_active_chan(f, app, vchunks[i / chans_in_column][i % chans_in_column], c);
     }
 }
 
-fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStats) {
+fn draw_active_chan<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, chan: &ChannelStats) {
     let vchunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -226,45 +213,30 @@ fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStat
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(vchunks[2]);
 
-    let chan_tittle = vec![Spans::from(vec![Span::styled(
-        chan.alias.clone(),
-        Style::default().fg(Color::White),
-    )])];
+    let chan_tittle = vec![Spans::from(vec![
+        super::pin_marker(app, chan),
+        Span::styled(
+            app.shown_alias(&chan.alias, &chan.node_id),
+            super::alias_style(app, chan, app.theme.fg()),
+        ),
+    ])];
     let paragraph = Paragraph::new(chan_tittle).alignment(Alignment::Left);
     f.render_widget(paragraph, vchunks[0]);
 
-    let capacity = chan.local + chan.remote;
-
-    let channel_ratio = if capacity == 0 {
-        0.0
-    } else {
-        chan.local as f64 / capacity as f64
-    };
-    let local = (chan.local / 1000).to_formatted_string(&Locale::en);
-    let remote = (chan.remote / 1000).to_formatted_string(&Locale::en);
-    let gauge = Gauge::default()
-        .gauge_style(
-            Style::default()
-                .fg(Color::Blue)
-                .bg(Color::Gray)
-                .add_modifier(Modifier::ITALIC),
-        )
-        .ratio(channel_ratio)
-        .label(format!("{}/{}", local, remote));
-    f.render_widget(gauge, vchunks[1]);
+    f.render_widget(super::balance_gauge(app, chan), vchunks[1]);
 
     let col0_spans = vec![
         Spans::from(vec![
             Span::from("Relays: ".to_owned()),
             Span::styled(
                 format!("{}", chan.relays_amount),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(app.theme.secondary()),
             ),
         ]),
         Spans::from(vec![
             Span::from("Fees: ".to_owned()),
             Span::styled(
-                (chan.relays_fees / 1000).to_formatted_string(&Locale::en),
+                app.short_amount(chan.relays_fees),
                 Style::default().fg(Color::Green),
             ),
         ]),
@@ -272,13 +244,19 @@ fn draw_active_chan<B: Backend>(f: &mut Frame<B>, area: Rect, chan: &ChannelStat
     let stats_col0 = Paragraph::new(col0_spans).alignment(Alignment::Left);
     f.render_widget(stats_col0, hchunks[0]);
 
-    let col1_spans = vec![Spans::from(vec![
+    let mut col1_spans = vec![Spans::from(vec![
         Span::from("Volume: ".to_owned()),
         Span::styled(
-            (chan.relays_volume / 1000).to_formatted_string(&Locale::en),
-            Style::default().fg(Color::Gray),
+            app.short_amount(chan.relays_volume),
+            Style::default().fg(app.theme.secondary()),
         ),
     ])];
+    for proposal in app.channel_proposals(&chan.chan_id) {
+        col1_spans.push(Spans::from(Span::styled(
+            proposal.to_string(),
+            Style::default().fg(Color::Magenta),
+        )));
+    }
     let stats_col1 = Paragraph::new(col1_spans).alignment(Alignment::Left);
     f.render_widget(stats_col1, hchunks[1]);
 }
@@ -288,12 +266,12 @@ fn draw_relays_amounts<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         .block(
             Block::default()
                 .title(format!(
-                    "24h relay count (max: {})",
-                    app.relays_maximum_count
+                    "24h hosted relay count (max: {})",
+                    app.hosted_relays_maximum_count
                 ))
                 .borders(Borders::LEFT | Borders::RIGHT),
         )
-        .data(&app.relays_amounts_line)
+        .data(&app.hosted_relays_amounts_line)
         .style(Style::default().fg(Color::Red));
     f.render_widget(sparkline, area);
 }
@@ -302,13 +280,14 @@ fn draw_relays_volumes<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let sparkline = Sparkline::default()
         .block(
             Block::default()
-                .title(format!(
-                    "24h relay volumes, (max: {} sats)",
-                    (app.relays_maximum_volume / 1000).to_formatted_string(&Locale::en)
+                .title(volumes_title(
+                    "hosted relay volumes",
+                    app.volumes_log_scale,
+                    app.hosted_relays_maximum_volume,
                 ))
                 .borders(Borders::LEFT | Borders::RIGHT),
         )
-        .data(&app.relays_volumes_line)
+        .data(&app.hosted_relays_volumes_line)
         .style(Style::default().fg(Color::Yellow));
     f.render_widget(sparkline, area);
 }
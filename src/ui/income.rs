@@ -0,0 +1,64 @@
+use num_format::{Locale, ToFormattedString};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table},
+    Frame,
+};
+
+use crate::api::payment::IncomingStatus;
+use crate::app::App;
+
+pub fn draw_income<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let visible_count = area.height.saturating_sub(3) as usize;
+    let payments = app.get_received_payments(visible_count);
+
+    let header = Row::new(vec!["Time", "Amount", "Status", "From", "Description", "Payment hash"])
+        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = payments
+        .iter()
+        .map(|p| {
+            let time = app.timezone.format(p.timestamp as i64, "%Y-%m-%d %H:%M");
+            Row::new(vec![
+                Cell::from(time)
+                    .style(Style::default().fg(app.theme.secondary())),
+                Cell::from(format!("{} sats", (p.amount / 1000).to_formatted_string(&Locale::en)))
+                    .style(Style::default().fg(Color::Green)),
+                status_cell(app, p.status.as_ref()),
+                Cell::from(p.sources.join(", ")),
+                Cell::from(p.description.clone().unwrap_or_else(|| "-".to_owned())),
+                Cell::from(p.payment_hash.clone()).style(Style::default().fg(app.theme.secondary())),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(16),
+        Constraint::Length(16),
+        Constraint::Length(8),
+        Constraint::Percentage(20),
+        Constraint::Percentage(30),
+        Constraint::Percentage(30),
+    ];
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .title(format!("Received payments ({})", app.audit.received.len()))
+                .borders(Borders::ALL),
+        )
+        .widths(&widths)
+        .column_spacing(2);
+    f.render_widget(table, area);
+}
+
+/// Status of the invoice, unknown until we query it from the node
+fn status_cell(app: &App, status: Option<&IncomingStatus>) -> Cell<'static> {
+    let (label, color) = match status {
+        Some(IncomingStatus::Received { .. }) => ("received", Color::Green),
+        Some(IncomingStatus::Pending) => ("pending", Color::Yellow),
+        Some(IncomingStatus::Expired) => ("expired", Color::Red),
+        Some(IncomingStatus::Unknown) | None => ("-", app.theme.secondary()),
+    };
+    Cell::from(label).style(Style::default().fg(color))
+}
@@ -1,15 +1,28 @@
 pub mod channels;
 pub mod dashboard;
+pub mod detail;
+pub mod errors;
 pub mod hosted;
 pub mod fiat;
+pub mod income;
+pub mod node;
+pub mod peers;
+pub mod routing;
 
 pub use channels::draw_channels;
 pub use dashboard::draw_dashboard;
+pub use detail::draw_detail;
+pub use errors::draw_errors;
 pub use hosted::draw_hosted;
 pub use fiat::draw_fiat;
+pub use income::draw_income;
+pub use node::draw_features;
+pub use peers::draw_peers;
+pub use routing::draw_routing;
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent},
+    cursor::Show,
+    event::{self, DisableFocusChange, EnableFocusChange, Event, KeyCode, KeyEvent},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -19,27 +32,43 @@ use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Clear, Paragraph, Tabs},
+    widgets::{BarChart, Block, Borders, Clear, Gauge, Paragraph, Tabs},
     Frame, Terminal,
 };
 use log::*;
 
-use super::app::{App, AppMutex};
+use super::api::NodePlugin;
+use super::app::{App, AppMutex, ChannelStats, ConnectionStatus, Tab};
+use super::commands;
+use super::supervise::lock;
 
-pub fn run_ui(app: AppMutex) -> Result<(), Box<dyn Error>> {
+/// Take over the terminal and run the UI. Without `altscreen` draw right in the current
+/// screen, so output printed by accident stays visible after exit.
+pub fn run_ui(app: AppMutex, altscreen: bool) -> Result<(), Box<dyn Error>> {
+    let focus_events = lock(&app).refresh_on_focus;
     // setup terminal
     enable_raw_mode()?;
-    execute!(io::stdout(), EnterAlternateScreen)?;
+    if altscreen {
+        execute!(io::stdout(), EnterAlternateScreen)?;
+    }
+    if focus_events {
+        execute!(io::stdout(), EnableFocusChange)?;
+    }
 
     // restore terminal
     defer! {
         let backend = CrosstermBackend::new(io::stdout());
         let mut terminal = Terminal::new(backend).unwrap();
         disable_raw_mode().unwrap();
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-        ).unwrap();
+        if focus_events {
+            execute!(terminal.backend_mut(), DisableFocusChange).unwrap();
+        }
+        if altscreen {
+            execute!(
+                terminal.backend_mut(),
+                LeaveAlternateScreen,
+            ).unwrap();
+        }
         terminal.show_cursor().unwrap();
     }
 
@@ -51,6 +80,19 @@ pub fn run_ui(app: AppMutex) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Give the terminal back to the shell after a panic of the UI thread, see
+/// `supervise::install_panic_hook`. Errors are ignored, we are crashing anyway.
+pub fn restore_terminal(altscreen: bool, focus_events: bool) {
+    let _ = disable_raw_mode();
+    if focus_events {
+        let _ = execute!(io::stdout(), DisableFocusChange);
+    }
+    if altscreen {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+    }
+    let _ = execute!(io::stdout(), Show);
+}
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mapp: AppMutex) -> io::Result<()> {
     let events = events(Duration::from_secs_f32(1.0));
     loop {
@@ -58,22 +100,49 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mapp: AppMutex) -> io::Result
 
         match events.recv().unwrap() {
             AppEvent::Input(key) => {
-                let mut app = mapp.lock().unwrap();
+                let mut app = lock(&mapp);
                 match key.code {
-                    KeyCode::Esc => return Ok(()),
+                    _ if app.search_focused => app.react_hotkey(key.code),
+                    KeyCode::Esc => {
+                        if !app.close_popup() {
+                            return Ok(());
+                        }
+                    }
                     KeyCode::Right => app.next_tab(),
                     KeyCode::Left => app.previous_tab(),
-                    KeyCode::Enter if !app.errors.is_empty() => app.errors = vec![],
+                    KeyCode::Enter if !app.errors.is_empty() => app.dismiss_top_error(),
+                    KeyCode::Delete if !app.errors.is_empty() => app.dismiss_errors(),
                     _ => app.react_hotkey(key.code),
                 }
+                if let Some(command) = app.queued_command.take() {
+                    commands::execute(mapp.clone(), app.client.clone(), command);
+                }
+                if let Some(query) = app.queued_route.take() {
+                    commands::find_route(mapp.clone(), app.client.clone(), query);
+                }
+            }
+            AppEvent::Resize(width, height) => lock(&mapp).resize(width, height),
+            AppEvent::FocusGained => {
+                let app = lock(&mapp);
+                if app.refresh_on_focus {
+                    debug!("Terminal gained focus, refreshing");
+                    app.refresh.notify_one();
+                }
+            }
+            AppEvent::Tick => {
+                let mut app = lock(&mapp);
+                app.tick_errors();
+                app.tick_flashes();
             }
-            AppEvent::Tick => (),
         }
     }
 }
 
 enum AppEvent {
     Input(KeyEvent),
+    /// New width and height of the terminal
+    Resize(u16, u16),
+    FocusGained,
     Tick,
 }
 
@@ -81,11 +150,15 @@ fn events(tick_rate: Duration) -> mpsc::Receiver<AppEvent> {
     let (tx, rx) = mpsc::channel();
     let keys_tx = tx.clone();
     thread::spawn(move || loop {
-        if let Ok(Event::Key(key)) = event::read() {
-            if let Err(err) = keys_tx.send(AppEvent::Input(key)) {
-                error!("{}", err);
-                return;
-            }
+        let app_event = match event::read() {
+            Ok(Event::Key(key)) => AppEvent::Input(key),
+            Ok(Event::Resize(width, height)) => AppEvent::Resize(width, height),
+            Ok(Event::FocusGained) => AppEvent::FocusGained,
+            _ => continue,
+        };
+        if let Err(err) = keys_tx.send(app_event) {
+            error!("{}", err);
+            return;
         }
     });
     thread::spawn(move || loop {
@@ -98,26 +171,49 @@ fn events(tick_rate: Duration) -> mpsc::Receiver<AppEvent> {
     rx
 }
 
+/// Smallest terminal size that our layouts are able to fit into
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 20;
+
 fn ui<B: Backend>(f: &mut Frame<B>, mapp: AppMutex) {
     let size = f.size();
-    let mut app = mapp.lock().unwrap();
-    app.resize(size.width);
+    let mut app = lock(&mapp);
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        draw_too_small(f, &app, size);
+        return;
+    }
+    if app.debug_panic {
+        panic!("Forced panic in draw, --debug-panic is set");
+    }
+    app.resize(size.width, size.height);
+    if app.last_update.is_none() && app.errors.is_empty() {
+        draw_splash(f, &app, size);
+        return;
+    }
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)].as_ref())
         .split(size);
 
-    let block = Block::default().style(Style::default().bg(Color::Black).fg(Color::White));
+    let block = Block::default().style(app.theme.style());
     f.render_widget(block, size);
     let titles = app
         .tabs
         .iter()
         .map(|t| {
-            let (first, rest) = t.split_at(1);
-            Spans::from(vec![
+            let (first, rest) = t.title().split_at(1);
+            let mut spans = vec![
                 Span::styled(first, Style::default().fg(Color::Yellow)),
                 Span::styled(rest, Style::default().fg(Color::Green)),
-            ])
+            ];
+            let proposals = app.tab_proposals_count(*t);
+            if proposals > 0 {
+                spans.push(Span::styled(
+                    format!(" ({})", proposals),
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
+            Spans::from(spans)
         })
         .collect();
     let tabs = Tabs::new(titles)
@@ -127,46 +223,326 @@ fn ui<B: Backend>(f: &mut Frame<B>, mapp: AppMutex) {
         .highlight_style(
             Style::default()
                 .add_modifier(Modifier::BOLD)
-                .bg(Color::Black),
+                .bg(app.theme.bg()),
         );
     f.render_widget(tabs, chunks[0]);
-    match app.tab_index {
-        0 => draw_dashboard(f, &app, chunks[1]),
-        1 => draw_channels(f, &app, chunks[1]),
-        2 => draw_peers(f, &app, chunks[1]),
-        3 => draw_onchain(f, &app, chunks[1]),
-        4 => draw_routing(f, &app, chunks[1]),
-        5 => draw_hosted(f, &app, chunks[1]),
-        6 => draw_fiat(f, &app, chunks[1]),
-        _ => unreachable!(),
+    match app.current_tab() {
+        Tab::Dashboard => draw_dashboard(f, &app, chunks[1]),
+        Tab::Channels => draw_channels(f, &app, chunks[1]),
+        Tab::Peers => draw_peers(f, &app, chunks[1]),
+        Tab::Onchain => draw_onchain(f, &app, chunks[1]),
+        Tab::Routing => draw_routing(f, &app, chunks[1]),
+        Tab::Hosted => match app.plugin_errors.get(&NodePlugin::HostedChannels) {
+            Some(error) => draw_plugin_error(f, &app, &NodePlugin::HostedChannels, error, chunks[1]),
+            None => draw_hosted(f, &app, chunks[1]),
+        },
+        Tab::Fiat => match app.plugin_errors.get(&NodePlugin::FiatChannels) {
+            Some(error) => draw_plugin_error(f, &app, &NodePlugin::FiatChannels, error, chunks[1]),
+            None => draw_fiat(f, &app, chunks[1]),
+        },
+        Tab::Log => draw_errors(f, &app, chunks[1]),
+        Tab::Income => draw_income(f, &app, chunks[1]),
     };
+    draw_status_bar(f, &app, chunks[2]);
+
+    if app.current_tab() == Tab::Channels && app.detail_open {
+        draw_detail(f, &app, centered_rect(70, 90, size));
+    }
+
+    if app.features_open {
+        draw_features(f, &app, centered_rect(70, 70, size));
+    }
+
+    if let Some(command) = &app.confirm_command {
+        let lines = vec![
+            Spans::from(command.confirmation(&app)),
+            Spans::from(""),
+            Spans::from(Span::styled("y - confirm, n - cancel", Style::default().fg(Color::Yellow))),
+        ];
+        draw_popup(f, &app, "Confirm command", lines, centered_rect(60, 20, size));
+    }
+
+    if let Some(result) = &app.command_result {
+        let result = app.shown_text(result);
+        let lines: Vec<Spans> = result.lines().map(|l| Spans::from(l.to_owned())).collect();
+        draw_popup(f, &app, "Command result", lines, centered_rect(70, 30, size));
+    }
 
     if !app.errors.is_empty() {
-        let errors: Vec<Spans> = app.errors.iter().map(|e| Spans::from(e.clone())).collect();
-        let block = Block::default()
-            .title("Errors occured")
-            .borders(Borders::ALL);
-        let paragraph = Paragraph::new(errors)
-            .block(block)
-            .alignment(Alignment::Left);
-        let area = centered_rect(80, 50, size);
-        f.render_widget(Clear, area); //this clears out the background
-        f.render_widget(paragraph, area);
+        let mut errors: Vec<Spans> =
+            app.errors.iter().map(|e| Spans::from(app.shown_text(e))).collect();
+        errors.push(Spans::from(""));
+        errors.push(Spans::from(Span::styled(
+            "Enter - dismiss top, Del - dismiss all",
+            Style::default().fg(Color::Yellow),
+        )));
+        let title = if app.errors_dropped > 0 {
+            format!(
+                "Errors occured ({} older dropped, the log tab has the latest {})",
+                app.errors_dropped,
+                App::ERROR_LOG_LIMIT
+            )
+        } else {
+            "Errors occured".to_owned()
+        };
+        draw_popup(f, &app, &title, errors, centered_rect(80, 50, size));
     }
 }
 
-fn draw_peers<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let block = Block::default().title("Peers").borders(Borders::ALL);
-    f.render_widget(block, area);
+/// Shown instead of the tab of an installed plugin whose endpoint fails
+fn draw_plugin_error<B: Backend>(
+    f: &mut Frame<B>,
+    app: &App,
+    plugin: &NodePlugin,
+    error: &str,
+    area: Rect,
+) {
+    let lines = vec![
+        Spans::from(Span::styled(
+            format!("The {} plugin is installed, but responds with an error", plugin),
+            Style::default().fg(Color::Yellow),
+        )),
+        Spans::from(Span::styled(error.to_owned(), Style::default().fg(app.theme.secondary()))),
+        Spans::from(Span::styled(
+            "Check the node logs, the tab comes back once the plugin works",
+            Style::default().fg(app.theme.secondary()),
+        )),
+    ];
+    let block = Block::default().borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block).alignment(Alignment::Center);
+    f.render_widget(paragraph, area);
 }
 
-fn draw_onchain<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let block = Block::default().title("Onchain").borders(Borders::ALL);
-    f.render_widget(block, area);
+/// Shown until the first poll of the node is finished
+fn draw_splash<B: Backend>(f: &mut Frame<B>, app: &App, size: Rect) {
+    let lines = vec![
+        Spans::from(Span::styled(
+            format!("Connecting to {}…", app.client.url()),
+            Style::default().fg(Color::Green),
+        )),
+        Spans::from(Span::styled(
+            app.connection.to_string(),
+            Style::default().fg(app.theme.secondary()),
+        )),
+    ];
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Length(lines.len() as u16),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(size);
+    let block = Block::default().style(app.theme.style());
+    f.render_widget(block, size);
+    let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(paragraph, vchunks[1]);
+}
+
+fn draw_too_small<B: Backend>(f: &mut Frame<B>, app: &App, size: Rect) {
+    let lines = vec![
+        Spans::from("Terminal is too small"),
+        Spans::from(format!(
+            "{}x{}, need at least {}x{}",
+            size.width, size.height, MIN_WIDTH, MIN_HEIGHT
+        )),
+    ];
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Length(lines.len() as u16),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(size);
+    let paragraph = Paragraph::new(lines)
+        .style(app.theme.style().fg(Color::Yellow))
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, vchunks[1]);
+}
+
+fn draw_status_bar<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    if app.search_focused {
+        let prompt = Spans::from(vec![
+            Span::styled(
+                format!(" {}: ", app.prompt.label()),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!("{}_", app.search_line)),
+        ]);
+        f.render_widget(Paragraph::new(prompt), area);
+        return;
+    }
+    let color = match app.connection {
+        ConnectionStatus::Unknown => app.theme.secondary(),
+        ConnectionStatus::Online => Color::Green,
+        ConnectionStatus::Retrying
+        | ConnectionStatus::BadResponse
+        | ConnectionStatus::Reconnecting => Color::Yellow,
+        ConnectionStatus::AuthFailed | ConnectionStatus::Unreachable => Color::Red,
+    };
+    let mut status = vec![
+        Span::styled(" ● ", Style::default().fg(color)),
+        Span::styled(app.connection.to_string(), Style::default().fg(color)),
+    ];
+    if let Some(secs) = app.retry_in() {
+        let label = if app.breaker_open { " — circuit open," } else { " —" };
+        status.push(Span::styled(
+            format!("{} retrying in {}s", label, secs),
+            Style::default().fg(color),
+        ));
+    }
+    if !matches!(
+        app.connection,
+        ConnectionStatus::Unknown | ConnectionStatus::Online | ConnectionStatus::Reconnecting
+    ) {
+        status.push(Span::styled("  F5 - reconnect", Style::default().fg(app.theme.secondary())));
+    }
+    let stuck = app.stuck_channels();
+    if let Some(longest) = stuck.first() {
+        status.push(Span::styled(
+            format!(
+                "  ⚠ {} stuck channels, longest with {}",
+                stuck.len(),
+                app.shown_alias(&longest.alias, &longest.node_id)
+            ),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if let Some(warning) = &app.version_warning {
+        status.push(Span::styled(
+            format!("  ⚠ {}", warning),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if app.anonymize {
+        status.push(Span::styled("  anonymized", Style::default().fg(app.theme.secondary())));
+    }
+    let status = Spans::from(status);
+    f.render_widget(Paragraph::new(status), area);
 }
 
-fn draw_routing<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let block = Block::default().title("Routing").borders(Borders::ALL);
+/// Bar chart of labeled values
+fn draw_bar_chart<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    title: &str,
+    buckets: &[(String, u64)],
+    color: Color,
+) {
+    let data: Vec<(&str, u64)> = buckets.iter().map(|(l, v)| (&l[..], *v)).collect();
+    // Fit all buckets into the area leaving a gap of one cell between bars
+    let bar_width = if data.is_empty() {
+        1
+    } else {
+        (area.width.saturating_sub(2) / data.len() as u16)
+            .saturating_sub(1)
+            .max(1)
+    };
+    let chart = BarChart::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(&data)
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(color))
+        .value_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(color)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Gauge of local and remote balances of the channel, filled part is the local balance
+/// unless the user flipped gauges to emphasize inbound liquidity
+fn balance_gauge(app: &App, chan: &ChannelStats) -> Gauge<'static> {
+    let (filled, other) = if app.gauge_remote {
+        (chan.remote, chan.local)
+    } else {
+        (chan.local, chan.remote)
+    };
+    let ratio = chan.channel_ratio(app.gauge_remote);
+    Gauge::default()
+        .gauge_style(
+            Style::default()
+                .fg(Color::Blue)
+                .bg(Color::Gray)
+                .add_modifier(Modifier::ITALIC),
+        )
+        .ratio(ratio)
+        .label(format!(
+            "{}/{} ({:.0}%)",
+            app.short_balance_sats(filled),
+            app.short_balance_sats(other),
+            100.0 * ratio
+        ))
+}
+
+/// Address with the result of the last reachability probe if we have one
+fn address_span(app: &App, address: &str) -> Span<'static> {
+    let shown = app.shown_address(address);
+    match app.reachability.get(address) {
+        Some(true) => Span::styled(format!("{} ✓", shown), Style::default().fg(Color::Green)),
+        Some(false) => Span::styled(format!("{} ✗", shown), Style::default().fg(Color::Red)),
+        None => Span::styled(shown, Style::default().fg(app.theme.secondary())),
+    }
+}
+
+/// Style of the channel alias, reversed for a few ticks after the local balance changed
+fn alias_style(app: &App, chan: &ChannelStats, color: Color) -> Style {
+    let style = Style::default().fg(color);
+    if app.is_flashing(chan) {
+        style.add_modifier(Modifier::REVERSED)
+    } else {
+        style
+    }
+}
+
+/// ARP value, marked provisional while the audit window is too short to trust it
+fn return_rate_span(app: &App, return_rate: f64) -> Span<'static> {
+    if app.return_rate_provisional {
+        Span::styled(
+            format!("{:.2}% (prov)", return_rate),
+            Style::default().fg(Color::Yellow),
+        )
+    } else {
+        Span::styled(
+            format!("{:.2}%", return_rate),
+            Style::default().fg(Color::Green),
+        )
+    }
+}
+
+/// Marker in front of aliases of pinned channels
+fn pin_marker(app: &App, chan: &ChannelStats) -> Span<'static> {
+    if app.is_pinned(chan) {
+        Span::styled("★ ", Style::default().fg(Color::Cyan))
+    } else {
+        Span::raw("")
+    }
+}
+
+fn draw_popup<B: Backend>(f: &mut Frame<B>, app: &App, title: &str, lines: Vec<Spans>, area: Rect) {
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(app.theme.style())
+        .alignment(Alignment::Left);
+    f.render_widget(Clear, area); //this clears out the background
+    f.render_widget(paragraph, area);
+}
+
+fn draw_onchain<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let block = Block::default().title("Onchain").borders(Borders::ALL);
     f.render_widget(block, area);
 }
 
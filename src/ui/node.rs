@@ -0,0 +1,76 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::api::{features::describe_bit, node::FeatureStatus};
+use crate::app::App;
+
+pub fn draw_features<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let mut lines = vec![Spans::from(Span::styled(
+        "Advertised addresses",
+        Style::default().fg(Color::Yellow),
+    ))];
+    if app.node_info.public_addresses.is_empty() {
+        lines.push(Spans::from(Span::styled(
+            "none, peers can't connect to the node",
+            Style::default().fg(Color::Red),
+        )));
+    }
+    for address in app.node_info.public_addresses.iter() {
+        lines.push(Spans::from(super::address_span(app, address)));
+    }
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        "Features",
+        Style::default().fg(Color::Yellow),
+    )));
+
+    let features = &app.node_info.features;
+    lines.extend(features.decode().into_iter().map(|feature| {
+        let status = match feature.status {
+            FeatureStatus::Mandatory => Span::styled("mandatory", Style::default().fg(Color::Yellow)),
+            FeatureStatus::Optional => Span::styled("optional ", Style::default().fg(Color::Green)),
+        };
+        let (bits, description) = match feature.known {
+            Some(known) => (
+                format!("{:>3}/{:<3}", known.bit, known.bit + 1),
+                Span::from(known.description),
+            ),
+            None => (
+                "  ?/?  ".to_owned(),
+                Span::styled(format!("{} (unknown)", feature.name), Style::default().fg(Color::Red)),
+            ),
+        };
+        Spans::from(vec![Span::raw(bits), Span::raw(" "), status, Span::raw(" "), description])
+    }));
+    for bit in features.unknown.iter() {
+        let description = match describe_bit(*bit) {
+            Some(known) => format!("{:>3}     unknown to the node: {}", bit, known.description),
+            None => format!("{:>3}     unknown feature bit", bit),
+        };
+        lines.push(Spans::from(Span::styled(description, Style::default().fg(Color::Red))));
+    }
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        "n - close",
+        Style::default().fg(Color::Yellow),
+    )));
+
+    let block = Block::default()
+        .title(format!(
+            "Node {}",
+            app.shown_alias(&app.node_info.alias, &app.node_info.node_id)
+        ))
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(app.theme.style())
+        .alignment(Alignment::Left);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
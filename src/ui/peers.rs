@@ -0,0 +1,108 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::api::peer::PeerState;
+use crate::app::{App, PeerSort};
+
+pub fn draw_peers<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(area);
+
+    let header = Row::new(vec![
+        "Alias",
+        "State",
+        "Channels",
+        "Last relay",
+        "Address",
+        "Known addresses",
+    ])
+    .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    let last_relay = app.peers_last_relay();
+    let rows: Vec<Row> = app
+        .get_sorted_peers()
+        .into_iter()
+        .map(|p| {
+            let color = match p.state {
+                PeerState::Connected => Color::Green,
+                PeerState::Instantiating => Color::Yellow,
+                PeerState::Disconnected => app.theme.secondary(),
+            };
+            Row::new(vec![
+                Cell::from(app.shown_alias(&app.get_peer_alias(&p.node_id), &p.node_id)),
+                Cell::from(format!("{:?}", p.state)).style(Style::default().fg(color)),
+                Cell::from(p.channels.to_string()),
+                Cell::from(last_relay.get(p.node_id.as_str()).map_or_else(
+                    || "-".to_owned(),
+                    |t| app.timezone.format(*t as i64, "%Y-%m-%d %H:%M"),
+                ))
+                .style(Style::default().fg(app.theme.secondary())),
+                Cell::from(p.address.as_ref().map_or_else(|| "-".to_owned(), |a| app.shown_address(a)))
+                    .style(Style::default().fg(app.theme.secondary())),
+                Cell::from(known_addresses(app, &p.node_id)),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Percentage(25),
+        Constraint::Length(14),
+        Constraint::Length(8),
+        Constraint::Length(16),
+        Constraint::Percentage(25),
+        Constraint::Percentage(30),
+    ];
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .title(match app.peers_sort {
+                    PeerSort::Node => format!("Peers ({})", app.peers.len()),
+                    PeerSort::LastRelay => format!("Peers by last relay ({})", app.peers.len()),
+                })
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().bg(app.theme.selected_bg()))
+        .widths(&widths)
+        .column_spacing(2);
+    let mut state = TableState::default();
+    if !app.peers.is_empty() {
+        state.select(Some(app.peers_selected));
+    }
+    f.render_stateful_widget(table, vchunks[0], &mut state);
+
+    let hint = if app.commands_enabled {
+        Span::styled(
+            "t - sort, C - connect to selected peer, D - disconnect from selected peer",
+            Style::default().fg(Color::Yellow),
+        )
+    } else {
+        Span::styled(
+            "t - sort, commands are disabled, see --enable-commands",
+            Style::default().fg(app.theme.secondary()),
+        )
+    };
+    f.render_widget(Paragraph::new(Spans::from(hint)), vchunks[1]);
+}
+
+/// Addresses of the peer from gossip separated by spaces
+fn known_addresses(app: &App, node_id: &str) -> Spans<'static> {
+    let addresses = app.get_known_addresses(node_id);
+    if addresses.is_empty() {
+        return Spans::from(Span::styled("-", Style::default().fg(app.theme.secondary())));
+    }
+    let mut spans = vec![];
+    for (i, address) in addresses.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" "));
+        }
+        spans.push(super::address_span(app, address));
+    }
+    Spans::from(spans)
+}
@@ -0,0 +1,44 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Color,
+    Frame,
+};
+
+use crate::app::App;
+
+pub fn draw_routing<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let vchunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(34),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    super::draw_bar_chart(
+        f,
+        vchunks[0],
+        "Hourly relay count",
+        &app.relays_hourly_counts,
+        Color::Red,
+    );
+    super::draw_bar_chart(
+        f,
+        vchunks[1],
+        "Hourly relay volumes, sats",
+        &app.relays_hourly_volumes,
+        Color::Yellow,
+    );
+    super::draw_bar_chart(
+        f,
+        vchunks[2],
+        &format!("Daily fees over {} days, sats", App::DAILY_FEES_DAYS),
+        &app.daily_fees,
+        Color::Green,
+    );
+}
@@ -0,0 +1,74 @@
+//! Valuation of balances of fiat channels
+use crate::api::hosted::FiatChannel;
+
+/// Where rates (msat per unit of fiat) to value balances of fiat channels come from
+pub trait RateSource {
+    fn rate(&self, chan: &FiatChannel, currency: &str) -> Option<u64>;
+}
+
+/// The rate that the channel itself uses, channels value balances differently
+pub struct ChannelRate;
+
+impl RateSource for ChannelRate {
+    fn rate(&self, chan: &FiatChannel, _currency: &str) -> Option<u64> {
+        Some(chan.data.commitments.last_cross_signed_state.rate).filter(|rate| *rate > 0)
+    }
+}
+
+/// The single reference rate from `--price-url` for all channels of its currency, so the
+/// channels are compared on equal footing
+pub struct OracleRate<'a> {
+    /// Price of 1 BTC
+    pub btc_price: Option<f64>,
+    pub currency: &'a str,
+}
+
+impl RateSource for OracleRate<'_> {
+    fn rate(&self, _chan: &FiatChannel, currency: &str) -> Option<u64> {
+        self.btc_price
+            .filter(|price| *price > 0.0 && self.currency.eq_ignore_ascii_case(currency))
+            .map(|price| (100_000_000_000.0 / price) as u64)
+    }
+}
+
+/// Rate source for fiat balances, chosen with `--fiat-valuation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FiatValuation {
+    /// At the rate of each channel
+    Channel,
+    /// At the BTC price of `--price-url`, mark-to-market
+    Oracle,
+}
+
+impl std::str::FromStr for FiatValuation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "channel" => Ok(FiatValuation::Channel),
+            "oracle" => Ok(FiatValuation::Oracle),
+            _ => Err(format!("expected channel or oracle, got `{}`", s)),
+        }
+    }
+}
+
+/// Balance of the client in units of fiat at the rate of the source, `None` if the source
+/// has no rate for the channel
+pub fn client_balance(source: &dyn RateSource, chan: &FiatChannel, currency: &str) -> Option<f64> {
+    let remote_balance = chan.data.commitments.last_cross_signed_state.remote_balance_msat;
+    source
+        .rate(chan, currency)
+        .map(|rate| remote_balance as f64 / rate as f64)
+}
+
+/// Balance of the client in units of fiat at the rate of the channel itself
+pub fn intrinsic_balance(chan: &FiatChannel) -> f64 {
+    let lcss = &chan.data.commitments.last_cross_signed_state;
+    lcss.remote_balance_msat as f64 / lcss.rate as f64
+}
+
+/// Balance of the client at the rate of the source. Channels that the source has no rate
+/// for, e.g. of other currencies than the oracle one, keep their own rate.
+pub fn valued_balance(source: &dyn RateSource, chan: &FiatChannel, currency: &str) -> f64 {
+    client_balance(source, chan, currency).unwrap_or_else(|| intrinsic_balance(chan))
+}
@@ -0,0 +1,16 @@
+use eclair_api::amount::saturating_sum;
+
+#[test]
+fn saturating_sum_small() {
+    assert_eq!(saturating_sum(std::iter::empty()), 0);
+    assert_eq!(saturating_sum([1, 2, 3].into_iter()), 6);
+}
+
+#[test]
+fn saturating_sum_near_max() {
+    assert_eq!(saturating_sum([u64::MAX - 1, 1].into_iter()), u64::MAX);
+    assert_eq!(saturating_sum([u64::MAX, 1].into_iter()), u64::MAX);
+    assert_eq!(saturating_sum([u64::MAX; 3].into_iter()), u64::MAX);
+    // Overflow in the middle must not be lost when later amounts are small
+    assert_eq!(saturating_sum([u64::MAX / 2 + 1, u64::MAX / 2 + 1, 0].into_iter()), u64::MAX);
+}
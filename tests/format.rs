@@ -0,0 +1,122 @@
+use eclair_api::format::{abbreviate, scale_line, volumes_title, AmountUnit, DisplayTz};
+use chrono::FixedOffset;
+
+fn offset(s: &str) -> Result<i32, String> {
+    match s.parse::<DisplayTz>()? {
+        DisplayTz::Fixed(offset) => Ok(offset.local_minus_utc()),
+        other => Err(format!("not an offset: {:?}", other)),
+    }
+}
+
+#[test]
+fn parse_display_tz_names() {
+    assert_eq!("UTC".parse::<DisplayTz>(), Ok(DisplayTz::Utc));
+    assert_eq!("local".parse::<DisplayTz>(), Ok(DisplayTz::Local));
+}
+
+#[test]
+fn parse_display_tz_offsets() {
+    assert_eq!(offset("+3"), Ok(3 * 3600));
+    assert_eq!(offset("+03:00"), Ok(3 * 3600));
+    assert_eq!(offset("-0530"), Ok(-(5 * 3600 + 30 * 60)));
+    assert_eq!(offset("+14:00"), Ok(14 * 3600));
+    assert_eq!(
+        "-12".parse::<DisplayTz>(),
+        Ok(DisplayTz::Fixed(FixedOffset::west(12 * 3600)))
+    );
+}
+
+#[test]
+fn reject_bad_display_tz_offsets() {
+    for bad in ["", "3", "+", "+15", "+03:60", "+123", "+aé1", "+é", "-1a", "+03:0x"] {
+        assert!(bad.parse::<DisplayTz>().is_err(), "accepted `{}`", bad);
+    }
+}
+
+#[test]
+fn display_tz_formats_in_offset() {
+    // 2022-05-04T13:45:10Z
+    let unix = 1651671910;
+    assert_eq!(DisplayTz::Utc.format(unix, "%H:%M"), "13:45");
+    let india = "+05:30".parse::<DisplayTz>().unwrap();
+    assert_eq!(india, DisplayTz::Fixed(FixedOffset::east(5 * 3600 + 30 * 60)));
+    assert_eq!(india.format(unix, "%H:%M"), "19:15");
+    let west = DisplayTz::Fixed(FixedOffset::west(3 * 3600));
+    assert_eq!(west.format(unix, "%H:%M"), "10:45");
+    // The shift moves the date too, 20:00 UTC is past midnight in India
+    assert_eq!(india.format(1651694400, "%d %H:%M"), "05 01:30");
+}
+
+#[test]
+fn abbreviate_boundaries() {
+    assert_eq!(abbreviate(0), "0");
+    assert_eq!(abbreviate(999), "999");
+    assert_eq!(abbreviate(1000), "1.0k");
+    assert_eq!(abbreviate(99_949), "99.9k");
+    assert_eq!(abbreviate(340_000), "340k");
+    // Would round to `1000k`, the next suffix is used instead
+    assert_eq!(abbreviate(999_600), "1.0M");
+    assert_eq!(abbreviate(1_500_000), "1.5M");
+    assert_eq!(abbreviate(2_000_000_000_000), "2.0T");
+    assert_eq!(abbreviate(u64::MAX), "18446744T");
+}
+
+#[test]
+fn amount_unit_format() {
+    assert_eq!(AmountUnit::Sat.format(1_234_567_890), "1,234,567 sats");
+    assert_eq!(AmountUnit::Bits.format(123_456_789), "1,234.56 bits");
+    assert_eq!(AmountUnit::MilliBtc.format(123_456_789_000), "1,234.56789 mBTC");
+    assert_eq!(AmountUnit::Btc.format(150_000_000_001), "1.50000000 BTC");
+    // Leading zeros of the fraction are kept
+    assert_eq!(AmountUnit::Btc.format(1_000), "0.00000001 BTC");
+    assert_eq!(AmountUnit::Bits.format(0), "0.00 bits");
+}
+
+#[test]
+fn amount_unit_format_short() {
+    assert_eq!(AmountUnit::Sat.format_short(999_000), "999 sats");
+    assert_eq!(AmountUnit::Sat.format_short(1_500_000_000), "1.5M sats");
+    assert_eq!(AmountUnit::Bits.format_short(123_456_789), "1,234.56 bits");
+}
+
+#[test]
+fn amount_unit_cycle() {
+    let mut unit = AmountUnit::Sat;
+    let mut labels = vec![];
+    for _ in 0..4 {
+        labels.push(unit.label());
+        unit = unit.next();
+    }
+    assert_eq!(labels, ["sats", "bits", "mBTC", "BTC"]);
+    assert_eq!(unit, AmountUnit::Sat);
+}
+
+#[test]
+fn scale_line_linear() {
+    assert_eq!(scale_line(&[0, 50, 100, 200], false), vec![0, 25, 50, 100]);
+    assert_eq!(scale_line(&[0, 0, 0], false), Vec::<u64>::new());
+    assert_eq!(scale_line(&[], false), Vec::<u64>::new());
+}
+
+#[test]
+fn scale_line_log() {
+    let line = scale_line(&[0, 1, 1_000, 1_000_000], true);
+    assert_eq!(line[0], 0);
+    assert_eq!(line[3], 100);
+    // Small values that are invisible in linear scale stay visible near the outlier
+    assert!(line[1] > 0);
+    assert_eq!(line[2], 50);
+    assert_eq!(scale_line(&[0, 1, 1_000, 1_000_000], false)[1..3], [0, 0]);
+}
+
+#[test]
+fn volumes_title_scale() {
+    assert_eq!(
+        volumes_title("relay volumes", true, 1_234_567_000),
+        "24h relay volumes, log scale (max: 1,234,567 sats)"
+    );
+    assert_eq!(
+        volumes_title("hosted relay volumes", false, 999),
+        "24h hosted relay volumes, linear (max: 0 sats)"
+    );
+}
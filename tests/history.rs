@@ -0,0 +1,51 @@
+use eclair_api::api::channel::ChannelState::{self, *};
+use eclair_api::history::{duration_in, run_start, Snapshot, OFFLINE_STATES};
+
+const NOW: i64 = 1651680000;
+
+/// Channel that flaps between offline and syncing every hour since `start`, cut to `limit`
+/// most recent records like the stored history
+fn flapping(start: i64, changes: usize, limit: usize) -> Vec<Snapshot<ChannelState>> {
+    let history: Vec<_> = (0..changes)
+        .map(|i| (start + i as i64 * 3600, if i % 2 == 0 { Offline } else { Syncing }))
+        .collect();
+    history[changes.saturating_sub(limit)..].to_vec()
+}
+
+#[test]
+fn duration_in_counts_from_start_of_run() {
+    let history = vec![(NOW - 300, Normal), (NOW - 200, Offline), (NOW - 100, Syncing)];
+    assert_eq!(duration_in(&history, &OFFLINE_STATES, None, NOW), Some(200));
+    assert_eq!(duration_in(&history, &[Syncing], None, NOW), Some(100));
+}
+
+#[test]
+fn duration_in_is_none_in_other_state() {
+    let history = vec![(NOW - 200, Offline), (NOW - 100, Normal)];
+    assert_eq!(duration_in(&history, &OFFLINE_STATES, Some(NOW - 200), NOW), None);
+    assert_eq!(duration_in(&[], &OFFLINE_STATES, Some(NOW - 200), NOW), None);
+}
+
+#[test]
+fn run_start_survives_history_limit() {
+    let start = NOW - 30 * 24 * 3600;
+    let history = flapping(start, 50, 20);
+    assert_eq!(history.len(), 20);
+    // Without the kept start only the records that fit the limit are counted
+    assert_eq!(run_start(&history, &OFFLINE_STATES, None), Some(start + 30 * 3600));
+    assert_eq!(run_start(&history, &OFFLINE_STATES, Some(start)), Some(start));
+}
+
+#[test]
+fn run_start_ignores_known_start_before_break() {
+    // The channel was back in between, the kept start belongs to an earlier run
+    let history = vec![(NOW - 300, Offline), (NOW - 200, Normal), (NOW - 100, Offline)];
+    assert_eq!(run_start(&history, &OFFLINE_STATES, Some(NOW - 300)), Some(NOW - 100));
+}
+
+#[test]
+fn run_start_prefers_earlier_start() {
+    let history = vec![(NOW - 100, Syncing)];
+    assert_eq!(run_start(&history, &OFFLINE_STATES, Some(NOW - 500)), Some(NOW - 500));
+    assert_eq!(run_start(&history, &OFFLINE_STATES, Some(NOW - 50)), Some(NOW - 100));
+}
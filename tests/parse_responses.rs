@@ -0,0 +1,291 @@
+//! Deserialize recorded eclair responses into the API types
+use eclair_api::api::{
+    audit::{self, AuditInfo, RelaySums},
+    channel::{ChannelInfo, ChannelKind, ChannelState, HtlcDirection},
+    hosted::{FcInfo, HcInfo},
+    node::{self, FeatureStatus, NetworkNode, NodeInfo, NodeNetwork},
+    payment::{IncomingPayment, IncomingStatus},
+    version::{Quirks, Version},
+};
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+}
+
+#[test]
+fn parse_getinfo() {
+    let info: NodeInfo = serde_json::from_str(&fixture("getinfo.json")).unwrap();
+    assert_eq!(
+        info.node_id,
+        "03a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90"
+    );
+    assert_eq!(info.alias, "tortoise-test");
+    assert_eq!(info.network, NodeNetwork::Mainnet);
+    assert_eq!(info.block_height, 734760);
+    assert_eq!(info.parsed_version(), Some(Version::new(0, 7, 0)));
+    assert_eq!(
+        info.features.activated.get("var_onion_optin"),
+        Some(&FeatureStatus::Mandatory)
+    );
+    assert_eq!(info.features.unknown, vec![32973]);
+}
+
+#[test]
+fn parse_channels() {
+    let channels: Vec<ChannelInfo> = serde_json::from_str(&fixture("channels.json")).unwrap();
+    assert_eq!(channels.len(), 2);
+
+    let normal = &channels[0];
+    assert_eq!(normal.state, ChannelState::Normal);
+    assert_eq!(normal.volume(), 5_000_000_000);
+    assert_eq!(normal.reserve(), Some(50_000_000));
+    assert_eq!(normal.spendable(), Some(2_950_000_000));
+    assert_eq!(normal.feerate(), Some(2500));
+    // Channel with data is normal whatever the plugins say
+    assert_eq!(normal.kind(false, false), ChannelKind::Normal);
+    let data = normal.data.as_ref().unwrap();
+    assert_eq!(data.short_channel_id.as_deref(), Some("734011x1553x1"));
+    let htlcs = &data.commitments.local_commit.spec.htlcs;
+    assert_eq!(htlcs.len(), 1);
+    assert_eq!(htlcs[0].direction, HtlcDirection::In);
+    assert_eq!(htlcs[0].add.amount_msat, 25_000_000);
+    let update = data.channel_update.as_ref().unwrap();
+    assert_eq!(update.fee_base_msat, 1000);
+    assert_eq!(update.fee_proportional_millionths, 100);
+
+    // States that we don't know yet must not break parsing of the whole list
+    assert_eq!(channels[1].state, ChannelState::Unknown);
+    assert!(channels[1].state.is_pending());
+}
+
+fn state(name: &str) -> ChannelState {
+    serde_json::from_str(&format!("\"{}\"", name)).unwrap()
+}
+
+#[test]
+fn classify_channel_states() {
+    let closed = state("CLOSED");
+    assert_eq!(closed, ChannelState::Closed);
+    assert!(closed.is_closed());
+    assert!(!closed.is_pending());
+    assert!(!closed.is_sleeping());
+    assert!(!closed.is_normal());
+
+    // Closing is still in progress, the channel is only closed when the state is Closed
+    let closing = state("CLOSING");
+    assert!(closing.is_pending());
+    assert!(!closing.is_closed());
+
+    let offline = state("OFFLINE");
+    assert!(offline.is_sleeping());
+    assert!(!offline.is_closed());
+    assert!(!offline.is_pending());
+
+    let normal = state("NORMAL");
+    assert!(normal.is_normal());
+    assert!(!normal.is_closed());
+    assert!(!normal.is_pending());
+    assert!(!normal.is_sleeping());
+}
+
+#[test]
+fn parse_unknown_channel_state() {
+    // Newer eclair may report states that we don't know, they are treated as pending
+    let unknown = state("WAIT_FOR_SOMETHING_NEW");
+    assert_eq!(unknown, ChannelState::Unknown);
+    assert!(unknown.is_pending());
+    assert!(!unknown.is_normal());
+    assert!(!unknown.is_sleeping());
+    assert!(!unknown.is_closed());
+}
+
+#[test]
+fn parse_channel_without_data() {
+    // Hosted channels are listed without data, but a normal channel may come without it too
+    let json = r#"[{
+        "nodeId": "02b7e8f9a0b1c2d3e4f5061728394a5b6c7d8e9fa0b1c2d3e4f5061728394a5b6c",
+        "channelId": "a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90",
+        "state": "NORMAL",
+        "data": null
+    }]"#;
+    let channels: Vec<ChannelInfo> = serde_json::from_str(json).unwrap();
+    let chan = &channels[0];
+    assert_eq!(chan.state, ChannelState::Normal);
+    assert!(chan.data.is_none());
+    assert_eq!(chan.volume(), 0);
+    assert_eq!(chan.reserve(), None);
+    assert_eq!(chan.spendable(), None);
+    assert_eq!(chan.feerate(), None);
+
+    // Only a hosted plugin can tell that the channel is fine
+    assert_eq!(chan.kind(true, true), ChannelKind::Hosted);
+    assert_eq!(chan.kind(true, false), ChannelKind::Hosted);
+    // While a plugin can't be asked the channel may be hosted, it's left unclassified
+    assert_eq!(chan.kind(false, false), ChannelKind::Unclassified);
+    // With all plugins answered the missing data is what has to be reported
+    assert_eq!(chan.kind(false, true), ChannelKind::Dataless);
+}
+
+#[test]
+fn parse_audit() {
+    let audit: AuditInfo = serde_json::from_str(&fixture("audit.json")).unwrap();
+    assert_eq!(audit.sent.len(), 1);
+    assert_eq!(audit.sent[0].recipient_amount, 150_000_000);
+    assert_eq!(audit.sent[0].parts[0].fees_paid, 1513);
+    assert_eq!(audit.received.len(), 1);
+    assert_eq!(audit.received[0].parts[0].amount, 21_000_000);
+
+    assert_eq!(audit.relayed.len(), 2);
+    let relay = &audit.relayed[0];
+    assert_eq!(relay._type, "channel");
+    assert_eq!(relay.amount_in - relay.amount_out, 12_000);
+    assert_eq!(relay.timestamp.unix, 1651671910);
+    assert_eq!(audit.relayed[1]._type, "trampoline");
+}
+
+#[test]
+fn relay_sums_count_multipart_payment_once() {
+    // Two records of one trampoline payment leaving through different channels
+    let audit: AuditInfo = serde_json::from_str(&fixture("audit_mpp.json")).unwrap();
+    assert_eq!(audit.relayed.len(), 3);
+    assert_ne!(audit.relayed[0].event_key(), audit.relayed[1].event_key());
+    let sums = audit.relay_sums(0, |_| true);
+    assert_eq!(sums.count, 2);
+    assert_eq!(sums.volume, 200_020_000 + 10_001_000);
+    assert_eq!(sums.fee, 20_000 + 1_000);
+
+    // Only relays after the start of the interval and passing the filter are summed
+    let recent = audit.relay_sums(1651680001, |_| true);
+    assert_eq!((recent.count, recent.volume, recent.fee), (1, 10_001_000, 1_000));
+    let big = audit.relay_sums(0, |r| r.amount_in > 100_000_000);
+    assert_eq!((big.count, big.volume, big.fee), (1, 200_020_000, 20_000));
+}
+
+#[test]
+fn relay_sums_count_overlapping_windows_once() {
+    let first: AuditInfo = serde_json::from_str(&fixture("audit_window_1.json")).unwrap();
+    let second: AuditInfo = serde_json::from_str(&fixture("audit_window_2.json")).unwrap();
+    // The relay at the end of the first window is the start of the second one
+    assert_eq!(first.relayed[1], second.relayed[0]);
+    let both = AuditInfo {
+        relayed: first.relayed.into_iter().chain(second.relayed).collect(),
+        ..AuditInfo::default()
+    };
+    let sums = both.relay_sums(0, |_| true);
+    assert_eq!(sums.count, 3);
+    assert_eq!(sums.volume, 30_003_000 + 40_004_000 + 10_001_000);
+    assert_eq!(sums.fee, 3_000 + 4_000 + 1_000);
+    assert_eq!(RelaySums::of(both.relayed.iter()).count, 4);
+}
+
+#[test]
+fn parse_nodes() {
+    let nodes: Vec<NetworkNode> = serde_json::from_str(&fixture("nodes.json")).unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[0].alias, "routing-peer");
+    assert_eq!(nodes[0].addresses.len(), 2);
+    assert_eq!(nodes[0].timestamp.unix, 1651659330);
+    assert!(nodes[1].alias.is_empty());
+    assert!(nodes[1].addresses.is_empty());
+}
+
+#[test]
+fn parse_nodes_skips_malformed() {
+    // The second entry lacks the features, it must not hide aliases of the others
+    let mut entries: Vec<serde_json::Value> = serde_json::from_str(&fixture("nodes.json")).unwrap();
+    entries.insert(
+        1,
+        serde_json::json!({
+            "nodeId": "02c0ffee0000000000000000000000000000000000000000000000000000000000",
+            "alias": "weird-gossip",
+            "timestamp": {"iso": "2022-05-02T00:00:00Z", "unix": 1651449600}
+        }),
+    );
+    let nodes = node::parse_nodes(&serde_json::to_string(&entries).unwrap()).unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[0].alias, "routing-peer");
+    assert_eq!(
+        nodes[1].node_id,
+        "0355d1b2c3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1"
+    );
+    assert!(node::parse_nodes("{}").is_err());
+}
+
+#[test]
+fn parse_received_info() {
+    let info: IncomingPayment = serde_json::from_str(&fixture("received_info.json")).unwrap();
+    assert_eq!(info.payment_request.description.as_deref(), Some("Order #1042"));
+    assert_eq!(info.payment_request.amount, Some(21_000_000));
+    assert_eq!(info.created_at.unix, 1651670000);
+    assert!(info.status.is_final());
+    match info.status {
+        IncomingStatus::Received { amount, received_at } => {
+            assert_eq!(amount, 21_000_000);
+            assert_eq!(received_at.unix, 1651670405);
+        }
+        status => panic!("Unexpected status {:?}", status),
+    }
+
+    let status: IncomingStatus = serde_json::from_str(r#"{"type": "expired"}"#).unwrap();
+    assert_eq!(status, IncomingStatus::Expired);
+    assert!(status.is_final());
+    let status: IncomingStatus = serde_json::from_str(r#"{"type": "pending"}"#).unwrap();
+    assert!(!status.is_final());
+    let status: IncomingStatus = serde_json::from_str(r#"{"type": "refunded"}"#).unwrap();
+    assert_eq!(status, IncomingStatus::Unknown);
+    assert!(!status.is_final());
+}
+
+#[test]
+fn parse_hosted_channels() {
+    let hc: HcInfo = serde_json::from_str(&fixture("hc_all.json")).unwrap();
+    assert_eq!(hc.channels.len(), 1);
+    let chan = hc.channels.values().next().unwrap();
+    assert_eq!(chan.state, ChannelState::Normal);
+    assert_eq!(chan.data.commitments.local_spec.to_local, 9_000_000_000);
+    assert_eq!(chan.data.commitments.local_spec.to_remote, 1_000_000_000);
+    assert_eq!(
+        chan.data.resize_proposal.as_ref().map(|p| p.new_capacity),
+        Some(20_000_000)
+    );
+    assert!(chan.data.override_proposal.is_none());
+}
+
+#[test]
+fn parse_fiat_channels() {
+    let fc: FcInfo = serde_json::from_str(&fixture("fc_all.json")).unwrap();
+    assert_eq!(fc.channels.len(), 1);
+    let chan = fc.channels.values().next().unwrap();
+    assert_eq!(chan.state, ChannelState::Normal);
+    let lcss = &chan.data.commitments.last_cross_signed_state;
+    assert!(lcss.is_host);
+    assert_eq!(lcss.rate, 3_500_000);
+    assert_eq!(lcss.local_balance_msat, 7_000_000_000);
+    assert_eq!(lcss.init_hosted_channel.channel_capacity_msat, 10_000_000_000);
+    assert_eq!(chan.data.last_oracle_state, Some(3_550_000));
+    // The fixture comes from a plugin version that doesn't tell the currency
+    assert_eq!(chan.data.currency, None);
+    let margin = chan.data.margin_proposal.as_ref().unwrap();
+    assert_eq!(margin.new_capacity, 12_000_000);
+    assert_eq!(margin.new_rate, 3_600_000);
+}
+
+#[test]
+fn quirks_of_versions() {
+    assert!(Quirks::of(Version::parse("0.6.2-e1f6a9a")).millis_timestamps);
+    assert!(!Quirks::of(Version::parse("0.7.0")).millis_timestamps);
+    assert!(!Quirks::of(Version::parse("0.8.0")).millis_timestamps);
+    // Unknown versions are parsed as recent ones
+    assert_eq!(Quirks::of(None), Quirks::default());
+}
+
+#[test]
+fn parse_audit_with_millis_timestamps() {
+    let legacy = fixture("audit_0_6.json");
+    let quirks = Quirks::of(Some(Version::new(0, 6, 2)));
+    let parsed = audit::parse_audit(&legacy, quirks).unwrap();
+    let recent = audit::parse_audit(&fixture("audit.json"), Quirks::default()).unwrap();
+    assert_eq!(parsed, recent);
+    assert!(audit::parse_audit(&legacy, Quirks::default()).is_err());
+}
@@ -0,0 +1,34 @@
+//! Plugins that are installed but fail on the node side
+use eclair_api::api::{Client, ErrorKind, NodePlugin};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Serve HTTP 500 to every request, like a node whose plugin throws
+async fn failing_node() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0; 4096];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\n\r\n")
+                .await;
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn hc_all_server_error() {
+    let client = Client::new(&failing_node().await, "", "password");
+    let err = client.get_hosted_channels().await.unwrap_err();
+    assert_eq!(err.status(), Some(500));
+    assert_eq!(err.kind(), ErrorKind::Server);
+
+    // The endpoint exists, so the plugin is present but erroring rather than unknown
+    let probe = client.probe_plugins(&[NodePlugin::HostedChannels]).await;
+    assert!(probe.supported.contains(&NodePlugin::HostedChannels));
+    assert!(probe.erroring.contains_key(&NodePlugin::HostedChannels));
+    assert!(probe.unknown.is_empty());
+}
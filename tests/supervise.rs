@@ -0,0 +1,65 @@
+use eclair_api::supervise::{self, install_panic_hook, lock, restart_on_exit};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[test]
+fn worker_panic_unwinds_without_restoring_terminal() {
+    let restored = Arc::new(AtomicUsize::new(0));
+    install_panic_hook({
+        let restored = restored.clone();
+        move || {
+            restored.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    let worker = thread::Builder::new()
+        .name("tokio-runtime-worker".to_owned())
+        .spawn(|| {
+            assert!(!supervise::is_ui_thread());
+            panic!("forced worker panic");
+        })
+        .unwrap();
+    // The process is still alive and the panic is reported to the owner of the handle
+    assert!(worker.join().is_err());
+    assert_eq!(restored.load(Ordering::SeqCst), 0);
+    let _ = std::panic::take_hook();
+}
+
+#[tokio::test]
+async fn panicking_task_is_restarted() {
+    let state = Arc::new(Mutex::new(0));
+    let (done, mut finished) = mpsc::unbounded_channel();
+    let (restarts, mut reasons) = mpsc::unbounded_channel();
+    let watchdog = tokio::spawn({
+        let state = state.clone();
+        async move {
+            restart_on_exit(
+                move || {
+                    let (state, done) = (state.clone(), done.clone());
+                    async move {
+                        let mut runs = lock(&state);
+                        *runs += 1;
+                        // Panic while holding the lock to poison it, like a buggy poller
+                        if *runs < 3 {
+                            panic!("forced poller panic");
+                        }
+                        let _ = done.send(*runs);
+                    }
+                },
+                Duration::from_millis(1),
+                move |reason| {
+                    let _ = restarts.send(reason);
+                },
+            )
+            .await
+        }
+    });
+    let runs = tokio::time::timeout(Duration::from_secs(5), finished.recv()).await;
+    watchdog.abort();
+    assert_eq!(runs.unwrap(), Some(3));
+    assert_eq!(reasons.recv().await.unwrap(), "panicked");
+    assert_eq!(reasons.recv().await.unwrap(), "panicked");
+    assert!(state.is_poisoned());
+}
@@ -0,0 +1,18 @@
+use eclair_api::theme::Theme;
+
+#[test]
+fn detect_theme_from_colorfgbg() {
+    assert_eq!(Theme::from_colorfgbg("0;15"), Some(Theme::Light));
+    assert_eq!(Theme::from_colorfgbg("15;default;0"), Some(Theme::Dark));
+    assert_eq!(Theme::from_colorfgbg("0;7"), Some(Theme::Light));
+    assert_eq!(Theme::from_colorfgbg("15;8"), Some(Theme::Dark));
+    assert_eq!(Theme::from_colorfgbg("0;11"), Some(Theme::Light));
+    assert_eq!(Theme::from_colorfgbg("0;14"), Some(Theme::Light));
+    // Bright red, green, blue and magenta are dark backgrounds
+    for bg in ["9", "10", "12", "13"] {
+        assert_eq!(Theme::from_colorfgbg(&format!("15;{}", bg)), Some(Theme::Dark));
+    }
+    assert_eq!(Theme::from_colorfgbg("garbage"), None);
+    assert_eq!(Theme::from_colorfgbg("0;default"), None);
+    assert_eq!(Theme::from_colorfgbg(""), None);
+}
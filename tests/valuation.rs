@@ -0,0 +1,55 @@
+use eclair_api::api::hosted::{FcInfo, FiatChannel};
+use eclair_api::valuation::{
+    client_balance, intrinsic_balance, valued_balance, ChannelRate, OracleRate, RateSource,
+};
+
+/// The fiat channel of the fixture, its rate is 3 500 000 msat per unit and the client has
+/// 3 000 000 000 msat
+fn fiat_channel() -> FiatChannel {
+    let path = format!("{}/tests/fixtures/fc_all.json", env!("CARGO_MANIFEST_DIR"));
+    let fc: FcInfo = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    fc.channels.into_values().next().unwrap()
+}
+
+fn oracle(btc_price: Option<f64>) -> OracleRate<'static> {
+    OracleRate {
+        btc_price,
+        currency: "EUR",
+    }
+}
+
+#[test]
+fn oracle_rate_of_matching_currency() {
+    let chan = fiat_channel();
+    // 1e11 msat in a BTC over 40 000 EUR
+    assert_eq!(oracle(Some(40_000.0)).rate(&chan, "EUR"), Some(2_500_000));
+    assert_eq!(oracle(Some(40_000.0)).rate(&chan, "eur"), Some(2_500_000));
+    assert_eq!(client_balance(&oracle(Some(40_000.0)), &chan, "EUR"), Some(1200.0));
+    assert_eq!(valued_balance(&oracle(Some(40_000.0)), &chan, "EUR"), 1200.0);
+}
+
+#[test]
+fn other_currency_keeps_channel_rate() {
+    let chan = fiat_channel();
+    assert_eq!(oracle(Some(40_000.0)).rate(&chan, "USD"), None);
+    assert_eq!(client_balance(&oracle(Some(40_000.0)), &chan, "USD"), None);
+    assert_eq!(valued_balance(&oracle(Some(40_000.0)), &chan, "USD"), intrinsic_balance(&chan));
+    assert_eq!(intrinsic_balance(&chan), 3_000_000_000.0 / 3_500_000.0);
+}
+
+#[test]
+fn missing_or_zero_price_keeps_channel_rate() {
+    let chan = fiat_channel();
+    for price in [None, Some(0.0), Some(-1.0)] {
+        assert_eq!(oracle(price).rate(&chan, "EUR"), None);
+        assert_eq!(valued_balance(&oracle(price), &chan, "EUR"), intrinsic_balance(&chan));
+    }
+}
+
+#[test]
+fn channel_rate_is_own_rate() {
+    let mut chan = fiat_channel();
+    assert_eq!(ChannelRate.rate(&chan, "USD"), Some(3_500_000));
+    chan.data.commitments.last_cross_signed_state.rate = 0;
+    assert_eq!(ChannelRate.rate(&chan, "EUR"), None);
+}